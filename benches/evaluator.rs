@@ -0,0 +1,73 @@
+//! Compares the throughput of the hand evaluators on random seven card hands.
+//!
+//! Run with `cargo bench`.
+
+use rusty_poker2::card::*;
+use rusty_poker2::evaluator;
+use rusty_poker2::hand_result::HandResult;
+
+use std::hint::black_box;
+use std::time::Instant;
+
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+use strum::IntoEnumIterator;
+
+const NUM_HANDS: usize = 1_000_000;
+
+fn report<T>(name: &str, hands: &[T], evaluate: impl Fn(&T) -> u16) {
+    let now = Instant::now();
+
+    let mut total = 0u64;
+    for hand in hands {
+        total += evaluate(black_box(hand)) as u64;
+    }
+    black_box(total);
+
+    let elapsed = now.elapsed();
+    println!(
+        "{:<24} {:>8.1} ms {:>12.0} hands/s",
+        name,
+        elapsed.as_secs_f64() * 1000.0,
+        hands.len() as f64 / elapsed.as_secs_f64()
+    );
+}
+
+fn main() {
+    let mut rng = thread_rng();
+    let mut deck = vec![];
+
+    for suit in Suit::iter() {
+        for value in Value::iter() {
+            deck.push(Card::new(suit, value));
+        }
+    }
+
+    let hands = (0..NUM_HANDS)
+        .map(|_| {
+            deck.shuffle(&mut rng);
+            deck[..7].to_vec()
+        })
+        .collect::<Vec<Vec<Card>>>();
+
+    // Build the lookup tables up front so they aren't counted below.
+    evaluator::strength(&hands[0]);
+
+    report("HandResult::check_hand", &hands, |hand| {
+        HandResult::check_hand(&mut hand.to_vec()).cards().len() as u16
+    });
+    report("HandResult::evaluate", &hands, |hand| {
+        HandResult::evaluate(hand).cards().len() as u16
+    });
+    report("evaluator::strength", &hands, |hand| {
+        evaluator::strength(hand)
+    });
+
+    let sets = hands
+        .iter()
+        .map(|hand| CardSet::from(&hand[..]))
+        .collect::<Vec<CardSet>>();
+    report("evaluator::set_strength", &sets, |set| {
+        evaluator::set_strength(*set)
+    });
+}
@@ -1,13 +1,27 @@
+use strum::IntoEnumIterator;
 use strum_macros::EnumIter;
 
 use self::Suit::*;
 use self::Value::*;
 
 use std::cmp::Ordering;
+use std::error::Error;
 use std::fmt;
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, Sub};
+use std::str::FromStr;
 
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::de::{self, Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 
+const SUITS: [Suit; 4] = [Clubs, Diamonds, Hearts, Spades];
+
+const RANKS: [Value; 13] = [
+    Two, Three, Four, Five, Six, Seven, Eight, Nine, Ten, Jack, Queen, King, Ace,
+];
+
 #[derive(Debug, Clone, Copy, EnumIter, PartialEq, Eq, Hash)]
 pub enum Suit {
     Clubs,
@@ -25,6 +39,15 @@ impl Suit {
             Spades => "Spades".to_string(),
         }
     }
+
+    /// The position of this suit in `Suit::iter()`, from 0 for Clubs to 3 for Spades.
+    pub fn index(&self) -> u8 {
+        *self as u8
+    }
+
+    pub fn from_index(index: u8) -> Suit {
+        SUITS[index as usize]
+    }
 }
 
 #[derive(Debug, Clone, Copy, EnumIter, PartialEq, Eq, Hash)]
@@ -98,6 +121,20 @@ impl Value {
             King => 13,
         }
     }
+
+    /// The rank of this value from 0 for a Two up to 12 for an Ace.
+    pub fn rank(&self) -> u8 {
+        self.value() as u8 - 2
+    }
+
+    pub fn from_rank(rank: u8) -> Value {
+        RANKS[rank as usize]
+    }
+
+    /// Whether this value is in a short deck, which has no Twos to Fives.
+    pub fn in_short_deck(&self) -> bool {
+        self.value() >= Six.value()
+    }
 }
 
 #[derive(Clone, Copy, Debug, Eq)]
@@ -108,10 +145,16 @@ pub struct Card {
 
 impl Card {
     pub fn new(suit: Suit, value: Value) -> Card {
-        Card {
-            suit: suit,
-            value: value,
-        }
+        Card { suit, value }
+    }
+
+    /// A compact index for this card, `suit * 13 + rank`, in the range 0..52.
+    pub fn index(&self) -> u8 {
+        self.suit.index() * 13 + self.value.rank()
+    }
+
+    pub fn from_index(index: u8) -> Card {
+        Card::new(Suit::from_index(index / 13), Value::from_rank(index % 13))
     }
 }
 
@@ -152,3 +195,663 @@ impl Serialize for Card {
         Ok(state)
     }
 }
+
+impl<'de> Deserialize<'de> for Card {
+    fn deserialize<D>(deserializer: D) -> Result<Card, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let text = String::deserialize(deserializer)?;
+        text.parse().map_err(de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseCardError {
+    Empty,
+    InvalidValue(String),
+    InvalidSuit(String),
+    MissingSuit(String),
+    DuplicateCard(Card),
+}
+
+impl fmt::Display for ParseCardError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseCardError::Empty => write!(f, "Expected a card but found nothing."),
+            ParseCardError::InvalidValue(text) => write!(
+                f,
+                "'{}' is not a card value, expected one of A K Q J T 10 9 8 7 6 5 4 3 2.",
+                text
+            ),
+            ParseCardError::InvalidSuit(text) => write!(
+                f,
+                "'{}' is not a suit, expected one of c d h s or ♣ ♦ ♥ ♠.",
+                text
+            ),
+            ParseCardError::MissingSuit(text) => write!(f, "'{}' is missing a suit.", text),
+            ParseCardError::DuplicateCard(card) => {
+                write!(f, "The {} appears more than once.", card)
+            }
+        }
+    }
+}
+
+impl Error for ParseCardError {}
+
+impl FromStr for Suit {
+    type Err = ParseCardError;
+
+    /// Accepts a suit's initial in either case, its full name or its symbol.
+    fn from_str(text: &str) -> Result<Suit, ParseCardError> {
+        match text.to_lowercase().as_str() {
+            "c" | "clubs" | "♣" | "♧" => Ok(Clubs),
+            "d" | "diamonds" | "♦" | "♢" => Ok(Diamonds),
+            "h" | "hearts" | "♥" | "♡" => Ok(Hearts),
+            "s" | "spades" | "♠" | "♤" => Ok(Spades),
+            "" => Err(ParseCardError::Empty),
+            _ => Err(ParseCardError::InvalidSuit(text.to_string())),
+        }
+    }
+}
+
+impl FromStr for Value {
+    type Err = ParseCardError;
+
+    /// Accepts a value's short name in either case, with Ten as either "T" or
+    /// "10", or its full name.
+    fn from_str(text: &str) -> Result<Value, ParseCardError> {
+        if text.is_empty() {
+            return Err(ParseCardError::Empty);
+        }
+
+        let lower = text.to_lowercase();
+
+        RANKS
+            .iter()
+            .find(|value| {
+                lower == value.short_name().to_lowercase()
+                    || lower == value.name().to_lowercase()
+                    || (**value == Ten && lower == "t")
+            })
+            .cloned()
+            .ok_or_else(|| ParseCardError::InvalidValue(text.to_string()))
+    }
+}
+
+impl FromStr for Card {
+    type Err = ParseCardError;
+
+    /// Parses a value followed by a suit, such as "Ah", "TD", "10d" or "A♥".
+    fn from_str(text: &str) -> Result<Card, ParseCardError> {
+        let text = text.trim();
+
+        let (suit_start, _) = text.char_indices().last().ok_or(ParseCardError::Empty)?;
+
+        if suit_start == 0 {
+            return Err(ParseCardError::MissingSuit(text.to_string()));
+        }
+
+        let value = text[..suit_start].parse()?;
+        let suit = text[suit_start..].parse()?;
+
+        Ok(Card::new(suit, value))
+    }
+}
+
+/// Parses a run of cards such as "AhKd 7c8c9c". Cards may be written back to
+/// back or split up by spaces or commas, and each card may appear only once.
+pub fn parse_hand(text: &str) -> Result<Vec<Card>, ParseCardError> {
+    let mut cards = Vec::<Card>::new();
+
+    for word in text.split(|c: char| c.is_whitespace() || c == ',') {
+        let mut rest = word;
+
+        while !rest.is_empty() {
+            // A card is "10" or a single character for the value, then the suit.
+            let value_len = if rest.starts_with("10") {
+                2
+            } else {
+                rest.chars().next().unwrap().len_utf8()
+            };
+            let card_len = match rest[value_len..].chars().next() {
+                Some(suit) => value_len + suit.len_utf8(),
+                None => return Err(ParseCardError::MissingSuit(rest.to_string())),
+            };
+
+            let card = rest[..card_len].parse::<Card>()?;
+            if cards.contains(&card) {
+                return Err(ParseCardError::DuplicateCard(card));
+            }
+
+            cards.push(card);
+            rest = &rest[card_len..];
+        }
+    }
+
+    Ok(cards)
+}
+
+/// A set of cards stored as a bitmask, with bit `Card::index` set for each card
+/// held. Each suit takes 13 bits, Two lowest, so `suit_mask` can pull out the
+/// ranks held in a suit with a single shift.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CardSet(u64);
+
+impl CardSet {
+    pub fn new() -> CardSet {
+        CardSet(0)
+    }
+
+    /// All 52 cards.
+    pub fn full() -> CardSet {
+        CardSet((1 << 52) - 1)
+    }
+
+    pub fn from_bits(bits: u64) -> CardSet {
+        CardSet(bits & Self::full().0)
+    }
+
+    pub fn bits(&self) -> u64 {
+        self.0
+    }
+
+    /// Adds `card`, returning whether it was not already in the set.
+    pub fn insert(&mut self, card: Card) -> bool {
+        let added = !self.contains(card);
+        self.0 |= 1 << card.index();
+        added
+    }
+
+    /// Removes `card`, returning whether it was in the set.
+    pub fn remove(&mut self, card: Card) -> bool {
+        let removed = self.contains(card);
+        self.0 &= !(1 << card.index());
+        removed
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.0 & (1 << card.index()) != 0
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    pub fn union(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 | other.0)
+    }
+
+    pub fn intersection(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 & other.0)
+    }
+
+    /// The cards in this set that are not in `other`.
+    pub fn difference(&self, other: CardSet) -> CardSet {
+        CardSet(self.0 & !other.0)
+    }
+
+    /// The ranks held in `suit`, with bit `Value::rank` set for each one.
+    pub fn suit_mask(&self, suit: Suit) -> u16 {
+        ((self.0 >> (suit.index() * 13)) & 0x1fff) as u16
+    }
+
+    /// Iterates over the cards in index order, Clubs first and Two up to Ace
+    /// within each suit.
+    pub fn iter(&self) -> CardSetIter {
+        CardSetIter(self.0)
+    }
+}
+
+pub struct CardSetIter(u64);
+
+impl Iterator for CardSetIter {
+    type Item = Card;
+
+    fn next(&mut self) -> Option<Card> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let index = self.0.trailing_zeros() as u8;
+        self.0 &= self.0 - 1;
+        Some(Card::from_index(index))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.0.count_ones() as usize;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for CardSetIter {}
+
+impl IntoIterator for CardSet {
+    type Item = Card;
+    type IntoIter = CardSetIter;
+
+    fn into_iter(self) -> CardSetIter {
+        self.iter()
+    }
+}
+
+impl FromIterator<Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = Card>>(iter: I) -> CardSet {
+        let mut set = CardSet::new();
+        for card in iter {
+            set.insert(card);
+        }
+        set
+    }
+}
+
+impl<'a> FromIterator<&'a Card> for CardSet {
+    fn from_iter<I: IntoIterator<Item = &'a Card>>(iter: I) -> CardSet {
+        iter.into_iter().cloned().collect()
+    }
+}
+
+impl From<Card> for CardSet {
+    fn from(card: Card) -> CardSet {
+        CardSet(1 << card.index())
+    }
+}
+
+impl From<&[Card]> for CardSet {
+    fn from(cards: &[Card]) -> CardSet {
+        cards.iter().collect()
+    }
+}
+
+impl BitOr for CardSet {
+    type Output = CardSet;
+
+    fn bitor(self, other: CardSet) -> CardSet {
+        self.union(other)
+    }
+}
+
+impl BitAnd for CardSet {
+    type Output = CardSet;
+
+    fn bitand(self, other: CardSet) -> CardSet {
+        self.intersection(other)
+    }
+}
+
+impl Sub for CardSet {
+    type Output = CardSet;
+
+    fn sub(self, other: CardSet) -> CardSet {
+        self.difference(other)
+    }
+}
+
+impl fmt::Display for CardSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let cards = self.iter().map(|c| c.to_string()).collect::<Vec<String>>();
+        write!(f, "[{}]", cards.join(", "))
+    }
+}
+
+/// A deck of cards, dealt from the top. Cards removed as dead stay out of the
+/// deck when it is reset.
+///
+/// Decks hold `Card`s unless made up of some other kind of card, such as a
+/// deck with jokers from `wild::WildRules::deck`.
+#[derive(Debug, Clone)]
+pub struct Deck<C = Card> {
+    cards: Vec<C>,
+    // Index of the top card, everything before it has been dealt or burnt.
+    top: usize,
+    burnt: Vec<C>,
+    dead: CardSet,
+    short: bool,
+}
+
+impl Deck {
+    /// A full, unshuffled deck in `Suit::iter()` then `Value::iter()` order.
+    pub fn new() -> Deck {
+        let mut deck = Deck::from_cards(Vec::with_capacity(52));
+        deck.reset();
+        deck
+    }
+
+    /// A short (6+) deck of 36 cards, with no Twos to Fives, in the same order
+    /// as `new`.
+    pub fn short() -> Deck {
+        let mut deck = Deck::new();
+        deck.short = true;
+        deck.reset();
+        deck
+    }
+
+    /// A deck that deals `top` first, in order, then the rest of a full deck
+    /// unshuffled. Useful for setting up a known deal. A reset puts the deck
+    /// back in the usual order.
+    pub fn stacked(top: &[Card]) -> Deck {
+        let mut deck = Deck::new();
+        deck.cards.retain(|card| !top.contains(card));
+        deck.cards.splice(0..0, top.iter().cloned());
+        deck
+    }
+
+    /// Puts every card that isn't dead back into the deck, unshuffled.
+    pub fn reset(&mut self) {
+        self.cards.clear();
+        self.top = 0;
+        self.burnt.clear();
+
+        for suit in Suit::iter() {
+            for value in Value::iter() {
+                let card = Card::new(suit, value);
+                if !self.dead.contains(card) && (!self.short || value.in_short_deck()) {
+                    self.cards.push(card);
+                }
+            }
+        }
+    }
+
+    /// Takes a known card out of the deck, for example one already seen in
+    /// another hand, and keeps it out after a reset. Returns whether the card was
+    /// still to be dealt.
+    pub fn remove(&mut self, card: Card) -> bool {
+        self.dead.insert(card);
+
+        match self.cards[self.top..].iter().position(|&c| c == card) {
+            Some(i) => {
+                self.cards.remove(self.top + i);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove_all(&mut self, cards: &[Card]) {
+        for card in cards {
+            self.remove(*card);
+        }
+    }
+
+    /// Lets cards removed with `remove` back in at the next reset.
+    pub fn clear_dead(&mut self) {
+        self.dead = CardSet::new();
+    }
+
+    pub fn remaining_set(&self) -> CardSet {
+        self.remaining().iter().collect()
+    }
+
+    pub fn contains(&self, card: Card) -> bool {
+        self.remaining().contains(&card)
+    }
+
+    pub fn dead(&self) -> CardSet {
+        self.dead
+    }
+}
+
+impl<C: Copy> Deck<C> {
+    /// A deck of `cards`, top card first.
+    pub(crate) fn from_cards(cards: Vec<C>) -> Deck<C> {
+        Deck {
+            cards,
+            top: 0,
+            burnt: vec![],
+            dead: CardSet::new(),
+            short: false,
+        }
+    }
+
+    /// Shuffles the cards that are still to be dealt.
+    pub fn shuffle<R: Rng + ?Sized>(&mut self, rng: &mut R) {
+        self.cards[self.top..].shuffle(rng);
+    }
+
+    /// Deals the top card, if there are any left.
+    pub fn deal_card(&mut self) -> Option<C> {
+        let card = *self.cards.get(self.top)?;
+        self.top += 1;
+        Some(card)
+    }
+
+    /// Deals the top `n` cards, or nothing if fewer than `n` are left.
+    pub fn deal(&mut self, n: usize) -> Option<Vec<C>> {
+        if self.len() < n {
+            return None;
+        }
+
+        let cards = self.cards[self.top..self.top + n].to_vec();
+        self.top += n;
+        Some(cards)
+    }
+
+    /// Deals the top card face down, out of play.
+    pub fn burn(&mut self) -> Option<C> {
+        let card = self.deal_card()?;
+        self.burnt.push(card);
+        Some(card)
+    }
+
+    /// The cards still to be dealt, top card first.
+    pub fn remaining(&self) -> &[C] {
+        &self.cards[self.top..]
+    }
+
+    pub fn burnt(&self) -> &[C] {
+        &self.burnt
+    }
+
+    /// The number of cards still to be dealt.
+    pub fn len(&self) -> usize {
+        self.cards.len() - self.top
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for Deck {
+    fn default() -> Deck {
+        Deck::new()
+    }
+}
+
+#[test]
+fn test_card_index_round_trip() {
+    let mut seen = CardSet::new();
+
+    for suit in Suit::iter() {
+        for value in Value::iter() {
+            let card = Card::new(suit, value);
+            assert!(card.index() < 52);
+            assert_eq!(Card::from_index(card.index()), card);
+            assert!(seen.insert(card));
+        }
+    }
+
+    assert_eq!(seen, CardSet::full());
+}
+
+#[test]
+fn test_card_set_operations() {
+    let ace = Card::new(Spades, Ace);
+    let two = Card::new(Clubs, Two);
+    let king = Card::new(Spades, King);
+
+    let mut set = [ace, two].iter().collect::<CardSet>();
+
+    assert_eq!(set.len(), 2);
+    assert!(set.contains(ace));
+    assert!(!set.contains(king));
+    assert!(!set.insert(ace));
+    assert!(set.insert(king));
+    assert_eq!(set.suit_mask(Spades), 0b1_1000_0000_0000);
+    assert_eq!(set.iter().collect::<Vec<Card>>(), vec![two, king, ace]);
+
+    let spades = set & CardSet::from(&[ace, king][..]);
+    assert_eq!(spades.len(), 2);
+    assert_eq!((set - spades).iter().collect::<Vec<Card>>(), vec![two]);
+    assert_eq!(spades | CardSet::from(two), set);
+
+    assert!(set.remove(two));
+    assert!(!set.remove(two));
+    assert_eq!(CardSet::full().difference(set).len(), 50);
+    assert!(CardSet::new().is_empty());
+}
+
+#[test]
+fn test_parse_card_notations() {
+    let ace_of_hearts = Card::new(Hearts, Ace);
+    let ten_of_diamonds = Card::new(Diamonds, Ten);
+
+    assert_eq!("Ah".parse(), Ok(ace_of_hearts));
+    assert_eq!("AH".parse(), Ok(ace_of_hearts));
+    assert_eq!("A♥".parse(), Ok(ace_of_hearts));
+    assert_eq!("Td".parse(), Ok(ten_of_diamonds));
+    assert_eq!("10d".parse(), Ok(ten_of_diamonds));
+    assert_eq!("10D".parse(), Ok(ten_of_diamonds));
+    assert_eq!("2c".parse(), Ok(Card::new(Clubs, Two)));
+
+    assert_eq!("".parse::<Card>(), Err(ParseCardError::Empty));
+    assert_eq!(
+        "A".parse::<Card>(),
+        Err(ParseCardError::MissingSuit("A".to_string()))
+    );
+    assert_eq!(
+        "1h".parse::<Card>(),
+        Err(ParseCardError::InvalidValue("1".to_string()))
+    );
+    assert_eq!(
+        "Ax".parse::<Card>(),
+        Err(ParseCardError::InvalidSuit("x".to_string()))
+    );
+}
+
+#[test]
+fn test_parse_hand() {
+    assert_eq!(
+        parse_hand("AhKd 7c8c10c"),
+        Ok(vec![
+            Card::new(Hearts, Ace),
+            Card::new(Diamonds, King),
+            Card::new(Clubs, Seven),
+            Card::new(Clubs, Eight),
+            Card::new(Clubs, Ten),
+        ])
+    );
+    assert_eq!(parse_hand("  "), Ok(vec![]));
+    assert_eq!(
+        parse_hand("Ah, Kd, AH"),
+        Err(ParseCardError::DuplicateCard(Card::new(Hearts, Ace)))
+    );
+    assert_eq!(
+        parse_hand("AhK"),
+        Err(ParseCardError::MissingSuit("K".to_string()))
+    );
+}
+
+#[test]
+fn test_serialized_cards_parse_back() {
+    let cards = Suit::iter()
+        .flat_map(|suit| Value::iter().map(move |value| Card::new(suit, value)))
+        .collect::<Vec<Card>>();
+
+    let mut wtr = csv::Writer::from_writer(vec![]);
+    for card in cards.iter() {
+        wtr.serialize([card]).unwrap();
+    }
+    let data = wtr.into_inner().unwrap();
+
+    let mut rdr = csv::ReaderBuilder::new()
+        .has_headers(false)
+        .from_reader(&data[..]);
+    let read = rdr
+        .deserialize::<[Card; 1]>()
+        .map(|row| row.unwrap()[0])
+        .collect::<Vec<Card>>();
+
+    assert_eq!(read, cards);
+}
+
+#[test]
+fn test_short_deck() {
+    let mut deck = Deck::short();
+    assert_eq!(deck.len(), 36);
+    assert!(deck
+        .remaining()
+        .iter()
+        .all(|card| card.value.in_short_deck()));
+
+    deck.remove(Card::new(Spades, Ace));
+    deck.deal(5).unwrap();
+    deck.reset();
+    assert_eq!(deck.len(), 35);
+    assert!(!deck.contains(Card::new(Hearts, Five)));
+}
+
+#[test]
+fn test_stacked_deck() {
+    let top = parse_hand("AsKs7d").unwrap();
+    let mut deck = Deck::stacked(&top);
+
+    assert_eq!(deck.len(), 52);
+    assert_eq!(deck.deal(3).unwrap(), top);
+    assert!(!deck.contains(top[0]));
+
+    deck.reset();
+    assert_eq!(deck.remaining(), Deck::new().remaining());
+}
+
+#[test]
+fn test_deck_deal_and_burn() {
+    let mut deck = Deck::new();
+
+    assert_eq!(deck.len(), 52);
+    assert_eq!(deck.deal_card(), Some(Card::new(Clubs, Ace)));
+    assert_eq!(deck.burn(), Some(Card::new(Clubs, Two)));
+    assert_eq!(
+        deck.deal(2),
+        Some(vec![Card::new(Clubs, Three), Card::new(Clubs, Four)])
+    );
+    assert_eq!(deck.burnt(), &[Card::new(Clubs, Two)]);
+    assert_eq!(deck.len(), 48);
+    assert!(!deck.contains(Card::new(Clubs, Ace)));
+
+    assert_eq!(deck.deal(49), None);
+    assert_eq!(deck.deal(48).map(|cards| cards.len()), Some(48));
+    assert!(deck.is_empty());
+    assert_eq!(deck.deal_card(), None);
+
+    deck.reset();
+    assert_eq!(deck.len(), 52);
+    assert!(deck.burnt().is_empty());
+}
+
+#[test]
+fn test_deck_dead_cards() {
+    let mut deck = Deck::new();
+    let ace = Card::new(Spades, Ace);
+    let king = Card::new(Hearts, King);
+
+    deck.remove_all(&[ace, king]);
+    deck.shuffle(&mut rand::thread_rng());
+
+    assert_eq!(deck.len(), 50);
+    assert!(!deck.contains(ace));
+    assert!(!deck.remaining_set().contains(king));
+    assert!(!deck.remove(ace));
+
+    deck.reset();
+    assert_eq!(deck.len(), 50);
+    assert_eq!(deck.remaining_set(), CardSet::full() - deck.dead());
+
+    deck.clear_dead();
+    deck.reset();
+    assert_eq!(deck.len(), 52);
+}
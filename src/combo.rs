@@ -0,0 +1,63 @@
+//! Counting and enumerating ways of choosing cards, for the evaluators and
+//! calculators that need to try every combination.
+
+use crate::card::*;
+
+/// The number of ways to choose `k` items from `n`.
+pub(crate) fn combinations(n: u64, k: u64) -> u64 {
+    if k > n {
+        return 0;
+    }
+
+    (0..k).fold(1, |total, i| total * (n - i) / (i + 1))
+}
+
+/// Calls `f` with every way of choosing `k` of `cards`, in order.
+pub(crate) fn for_each_combination(cards: &[Card], k: usize, mut f: impl FnMut(&[Card])) {
+    if k > cards.len() {
+        return;
+    }
+
+    let mut indices = (0..k).collect::<Vec<usize>>();
+    let mut chosen = indices.iter().map(|&i| cards[i]).collect::<Vec<Card>>();
+
+    loop {
+        f(&chosen);
+
+        // Find the rightmost index that can still move up, then reset every
+        // index after it to follow on directly.
+        let mut i = k;
+        loop {
+            if i == 0 {
+                return;
+            }
+            i -= 1;
+            if indices[i] < cards.len() - k + i {
+                break;
+            }
+        }
+
+        indices[i] += 1;
+        chosen[i] = cards[indices[i]];
+        for j in i + 1..k {
+            indices[j] = indices[j - 1] + 1;
+            chosen[j] = cards[indices[j]];
+        }
+    }
+}
+
+#[test]
+fn test_combinations() {
+    assert_eq!(combinations(48, 5), 1_712_304);
+    assert_eq!(combinations(45, 0), 1);
+    assert_eq!(combinations(3, 4), 0);
+
+    let mut count = 0;
+    let mut last = vec![];
+    for_each_combination(&parse_hand("AhKhQhJhTh9h").unwrap(), 3, |chosen| {
+        count += 1;
+        last = chosen.to_vec();
+    });
+    assert_eq!(count, 20);
+    assert_eq!(last, parse_hand("JhTh9h").unwrap());
+}
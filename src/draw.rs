@@ -0,0 +1,200 @@
+//! Five card draw: each player is dealt five cards face down, may throw away
+//! any of them and draw as many replacements from the deck, then the best
+//! hand wins.
+//!
+//! Which cards to throw away is up to a `DiscardStrategy`, so strategies can
+//! be played against each other. A closure taking the hand and returning the
+//! cards to throw away works as a strategy too.
+
+use crate::card::*;
+use crate::hand_result::HandResult;
+use crate::player::Player;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+
+pub const HAND_SIZE: usize = 5;
+/// Five cards each plus up to five more on the draw for five players takes
+/// at most 50 cards, so the deck never runs short.
+pub const MAX_PLAYERS: usize = 5;
+
+const FLUSH_DRAW: usize = 4;
+const STRAIGHT_DRAW: usize = 4;
+
+/// Decides which cards to throw away before the draw.
+pub trait DiscardStrategy {
+    /// Returns the cards of `hand` to throw away, from none to all five.
+    fn discard(&self, hand: &[Card]) -> Vec<Card>;
+}
+
+impl<F: Fn(&[Card]) -> Vec<Card>> DiscardStrategy for F {
+    fn discard(&self, hand: &[Card]) -> Vec<Card> {
+        self(hand)
+    }
+}
+
+/// The strategies built in to the simulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// Never draws.
+    StandPat,
+    /// Keeps whatever pair or better is made, and stands pat on a straight or
+    /// better. Without a pair it draws one to four to a flush, then to four to
+    /// an open ended straight, and otherwise keeps its highest card and draws
+    /// four.
+    KeepMadeHands,
+}
+
+impl DiscardStrategy for Strategy {
+    fn discard(&self, hand: &[Card]) -> Vec<Card> {
+        let keep = match self {
+            Strategy::StandPat => hand.to_vec(),
+            Strategy::KeepMadeHands => keep_made_hands(hand),
+        };
+
+        hand.iter()
+            .filter(|card| !keep.contains(card))
+            .cloned()
+            .collect()
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Strategy, String> {
+        match text {
+            "stand-pat" => Ok(Strategy::StandPat),
+            "keep-made" => Ok(Strategy::KeepMadeHands),
+            _ => Err(format!("'{}' is not a draw strategy.", text)),
+        }
+    }
+}
+
+/// The cards `Strategy::KeepMadeHands` keeps.
+fn keep_made_hands(hand: &[Card]) -> Vec<Card> {
+    let result = HandResult::check_hand(&mut hand.to_vec());
+
+    // The cards making the hand come first in `cards`, ahead of the kickers.
+    let made = match result.category() {
+        0 => 0,
+        1 => 2,
+        2 => 4,
+        3 => 3,
+        _ => HAND_SIZE,
+    };
+    if made > 0 {
+        return result.cards()[..made].to_vec();
+    }
+
+    let mut suits = HashMap::<Suit, Vec<Card>>::new();
+    for card in hand.iter() {
+        suits.entry(card.suit).or_default().push(*card);
+    }
+    if let Some(flush_draw) = suits.into_values().find(|cards| cards.len() >= FLUSH_DRAW) {
+        return flush_draw;
+    }
+
+    // With no pair every value is different, so four in a row that neither start
+    // nor end with an Ace can be filled at either end.
+    let mut sorted = hand.to_vec();
+    sorted.sort();
+    for run in sorted.windows(STRAIGHT_DRAW) {
+        let (low, high) = (run[0].value.value(), run[STRAIGHT_DRAW - 1].value.value());
+        if high - low == STRAIGHT_DRAW as u32 - 1 && high < Value::Ace.value() {
+            return run.to_vec();
+        }
+    }
+
+    vec![*sorted.last().unwrap()]
+}
+
+/// Deals five cards face down to each player, a round at a time.
+///
+/// Panics if the deck runs out.
+pub fn deal(deck: &mut Deck, players: &mut [Player]) {
+    for _ in 0..HAND_SIZE {
+        for player in players.iter_mut() {
+            player.add_card(deck.deal_card().expect("The deck ran out of cards."));
+        }
+    }
+}
+
+/// Throws away the cards `strategy` picks from the player's hand and draws as
+/// many replacements from `deck`. Returns the cards thrown away.
+///
+/// Panics if the deck runs out, which can't happen with `MAX_PLAYERS` or fewer
+/// drawing from a full deck.
+pub fn draw(deck: &mut Deck, player: &mut Player, strategy: &dyn DiscardStrategy) -> Vec<Card> {
+    // Only cards actually held can be thrown away.
+    let discards = strategy
+        .discard(&player.cards)
+        .into_iter()
+        .filter(|card| player.cards.contains(card))
+        .collect::<Vec<Card>>();
+
+    player.remove_cards(&discards);
+    player.add_cards(
+        deck.deal(discards.len())
+            .expect("The deck ran out of cards."),
+    );
+
+    discards
+}
+
+#[cfg(test)]
+fn discards(strategy: Strategy, text: &str) -> Vec<Card> {
+    let mut discards = strategy.discard(&parse_hand(text).unwrap());
+    discards.sort_by_key(Card::index);
+    discards
+}
+
+#[test]
+fn test_keep_made_hands() {
+    let strategy = Strategy::KeepMadeHands;
+    let cards = |text: &str| {
+        let mut cards = parse_hand(text).unwrap();
+        cards.sort_by_key(Card::index);
+        cards
+    };
+
+    // A straight or better stands pat.
+    assert_eq!(discards(strategy, "9c8d7h6s5c"), vec![]);
+    assert_eq!(discards(strategy, "2h7h9hJhKh"), vec![]);
+    // Trips and two pair throw away their kickers, a pair its three kickers.
+    assert_eq!(discards(strategy, "QcQdQhAs2c"), cards("As2c"));
+    assert_eq!(discards(strategy, "QcQd3h3sAc"), cards("Ac"));
+    assert_eq!(discards(strategy, "5c5dAhKs9c"), cards("AhKs9c"));
+    // Four to a flush draws one, ahead of an open ended straight.
+    assert_eq!(discards(strategy, "2h7h9hJhTs"), cards("Ts"));
+    assert_eq!(discards(strategy, "8c9dThJsAs"), cards("As"));
+    // Four to the Ace is only a one ended draw, so it keeps the Ace alone.
+    assert_eq!(discards(strategy, "AcKdQhJs3s"), cards("KdQhJs3s"));
+}
+
+#[test]
+fn test_stand_pat() {
+    assert_eq!(discards(Strategy::StandPat, "AcKdQhJs3s"), vec![]);
+}
+
+#[test]
+fn test_draw_replaces_discards() {
+    let mut deck = Deck::new();
+    let mut player = Player::new("Player 1".to_string());
+    player.add_cards(parse_hand("5c5dAhKs9c").unwrap());
+    deck.remove_all(&player.cards);
+
+    let thrown = draw(&mut deck, &mut player, &Strategy::KeepMadeHands);
+    assert_eq!(thrown.len(), 3);
+    assert_eq!(player.cards.len(), HAND_SIZE);
+    assert!(player.cards.contains(&Card::new(Suit::Clubs, Value::Five)));
+    assert!(thrown.iter().all(|card| !player.cards.contains(card)));
+    assert_eq!(deck.len(), 52 - HAND_SIZE - 3);
+
+    // Any closure can be a strategy, and cards not held are ignored.
+    let thrown = draw(&mut deck, &mut player, &|hand: &[Card]| {
+        vec![hand[0], Card::new(Suit::Spades, Value::Two)]
+    });
+    assert_eq!(thrown.len(), 1);
+    assert_eq!(player.cards.len(), HAND_SIZE);
+}
@@ -0,0 +1,588 @@
+//! Equity of Hold'em hands and ranges against each other.
+//!
+//! Every way of dealing the hands and completing the board is played out when
+//! there are few enough of them, otherwise deals are sampled at random and each
+//! equity comes with a 95% confidence interval.
+
+use crate::card::*;
+use crate::combo::{combinations, for_each_combination};
+use crate::evaluator;
+use crate::range::Range;
+
+use std::error::Error;
+use std::fmt;
+
+use rand::distributions::{Distribution, WeightedIndex};
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+const BOARD_SIZE: usize = 5;
+// Standard normal quantile for a two-sided 95% confidence interval.
+const Z_95: f64 = 1.96;
+// Give up sampling hands from ranges after this many clashing deals in a row.
+const MAX_REDEALS: usize = 10_000;
+
+#[derive(Debug, Clone)]
+pub struct EquityOptions {
+    /// Deal out every board, for every way of dealing the hands, when there are
+    /// at most this many, otherwise sample.
+    pub max_exhaustive_boards: u64,
+    /// How many deals to sample when not dealing them all out.
+    pub samples: u64,
+    /// Seed for sampling, random if not given.
+    pub seed: Option<u64>,
+}
+
+impl Default for EquityOptions {
+    fn default() -> EquityOptions {
+        EquityOptions {
+            max_exhaustive_boards: 2_000_000,
+            samples: 500_000,
+            seed: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlayerEquity {
+    /// Fraction of deals won outright.
+    pub win: f64,
+    /// Fraction of deals split with at least one other player.
+    pub tie: f64,
+    /// Average share of the pot, counting a split as the fraction won.
+    pub equity: f64,
+    /// Half the width of the 95% confidence interval around `equity`, or 0 if
+    /// every deal was played out.
+    pub margin: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Equity {
+    pub players: Vec<PlayerEquity>,
+    /// How many boards were evaluated, across every way of dealing the hands.
+    pub boards: u64,
+    /// Whether every possible deal was evaluated rather than a sample.
+    pub exhaustive: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EquityError {
+    TooFewPlayers,
+    BoardTooLarge(usize),
+    DuplicateCard(Card),
+    // The range of this player, numbered from 0, has no combos left once the
+    // board and dead cards are taken out.
+    EmptyRange(usize),
+    // The ranges can't be dealt out together without sharing a card.
+    RangesClash,
+    // There aren't enough cards left to deal this many players and a board.
+    NotEnoughCards(usize),
+}
+
+impl fmt::Display for EquityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EquityError::TooFewPlayers => write!(f, "Equity needs at least two hands."),
+            EquityError::BoardTooLarge(n) => {
+                write!(f, "A board has at most {} cards, got {}.", BOARD_SIZE, n)
+            }
+            EquityError::DuplicateCard(card) => {
+                write!(f, "The {} appears more than once.", card)
+            }
+            EquityError::EmptyRange(player) => write!(
+                f,
+                "Player {} has no hands left once the known cards are taken out.",
+                player + 1
+            ),
+            EquityError::RangesClash => {
+                write!(f, "The ranges can't be dealt without sharing a card.")
+            }
+            EquityError::NotEnoughCards(players) => write!(
+                f,
+                "There aren't enough cards left to deal {} players and the board.",
+                players
+            ),
+        }
+    }
+}
+
+impl Error for EquityError {}
+
+/// Running totals of how each player fared over the boards seen so far. Each
+/// board counts for its weight, the chance of the hands it was dealt with.
+#[derive(Debug, Clone)]
+pub(crate) struct Tally {
+    wins: Vec<f64>,
+    ties: Vec<f64>,
+    shares: Vec<f64>,
+    squared_shares: Vec<f64>,
+    total_weight: f64,
+    boards: u64,
+}
+
+impl Tally {
+    pub(crate) fn new(num_players: usize) -> Tally {
+        Tally {
+            wins: vec![0.0; num_players],
+            ties: vec![0.0; num_players],
+            shares: vec![0.0; num_players],
+            squared_shares: vec![0.0; num_players],
+            total_weight: 0.0,
+            boards: 0,
+        }
+    }
+
+    /// Records one showdown, given the strength of each player's hand.
+    pub(crate) fn add(&mut self, strengths: &[u16], weight: f64) {
+        let best = *strengths.iter().max().unwrap();
+        let num_winners = strengths.iter().filter(|&&s| s == best).count();
+        let share = 1.0 / num_winners as f64;
+
+        for (i, &strength) in strengths.iter().enumerate() {
+            if strength != best {
+                continue;
+            }
+
+            if num_winners == 1 {
+                self.wins[i] += weight;
+            } else {
+                self.ties[i] += weight;
+            }
+            self.shares[i] += share * weight;
+            self.squared_shares[i] += share * share * weight;
+        }
+
+        self.total_weight += weight;
+        self.boards += 1;
+    }
+
+    pub(crate) fn finish(&self, exhaustive: bool) -> Equity {
+        let n = self.total_weight;
+
+        let players = (0..self.wins.len())
+            .map(|i| {
+                let equity = self.shares[i] / n;
+                // Sampled boards all have a weight of 1.
+                let margin = if exhaustive || self.boards < 2 {
+                    0.0
+                } else {
+                    let variance = (self.squared_shares[i] / n - equity * equity).max(0.0);
+                    Z_95 * (variance / (n - 1.0)).sqrt()
+                };
+
+                PlayerEquity {
+                    win: self.wins[i] / n,
+                    tie: self.ties[i] / n,
+                    equity,
+                    margin,
+                }
+            })
+            .collect();
+
+        Equity {
+            players,
+            boards: self.boards,
+            exhaustive,
+        }
+    }
+}
+
+/// Works out the equity of each of `hands` given the cards already on the
+/// `board` and any `dead` cards known to be out of the deck.
+pub fn calculate(
+    hands: &[[Card; 2]],
+    board: &[Card],
+    dead: &[Card],
+    options: &EquityOptions,
+) -> Result<Equity, EquityError> {
+    let mut seen = CardSet::new();
+    for card in hands.iter().flatten().chain(board).chain(dead) {
+        if !seen.insert(*card) {
+            return Err(EquityError::DuplicateCard(*card));
+        }
+    }
+
+    let ranges = hands
+        .iter()
+        .map(|&hand| Range::from(hand))
+        .collect::<Vec<_>>();
+    calculate_ranges(&ranges, board, dead, options)
+}
+
+/// Works out the equity of each of `ranges`, with each player dealt one combo
+/// from their range in proportion to its weight. A known hand is a range of one
+/// combo, so this also covers a hand against one or more ranges.
+pub fn calculate_ranges(
+    ranges: &[Range],
+    board: &[Card],
+    dead: &[Card],
+    options: &EquityOptions,
+) -> Result<Equity, EquityError> {
+    if ranges.len() < 2 {
+        return Err(EquityError::TooFewPlayers);
+    }
+
+    if board.len() > BOARD_SIZE {
+        return Err(EquityError::BoardTooLarge(board.len()));
+    }
+
+    let mut known = CardSet::new();
+    for card in board.iter().chain(dead) {
+        if !known.insert(*card) {
+            return Err(EquityError::DuplicateCard(*card));
+        }
+    }
+
+    let ranges = ranges
+        .iter()
+        .map(|range| range.without(known))
+        .collect::<Vec<Range>>();
+    if let Some(player) = ranges.iter().position(|range| range.total_weight() <= 0.0) {
+        return Err(EquityError::EmptyRange(player));
+    }
+
+    let deck = (CardSet::full() - known).iter().collect::<Vec<Card>>();
+    let to_deal = BOARD_SIZE - board.len();
+    let hole_cards = 2 * ranges.len();
+    if hole_cards + to_deal > deck.len() {
+        return Err(EquityError::NotEnoughCards(ranges.len()));
+    }
+    let num_boards = combinations((deck.len() - hole_cards) as u64, to_deal as u64);
+    let num_deals = ranges.iter().fold(num_boards, |total, range| {
+        total.saturating_mul(range.len() as u64)
+    });
+    let exhaustive = num_deals <= options.max_exhaustive_boards;
+
+    let mut tally = Tally::new(ranges.len());
+    let mut strengths = vec![0; ranges.len()];
+    let mut full_board = board.to_vec();
+
+    let mut showdown = |hands: &[[Card; 2]], runout: &[Card], weight: f64, tally: &mut Tally| {
+        full_board.truncate(board.len());
+        full_board.extend_from_slice(runout);
+
+        for (strength, hand) in strengths.iter_mut().zip(hands) {
+            *strength = evaluator::strength(&seven_cards(hand, &full_board));
+        }
+
+        tally.add(&strengths, weight);
+    };
+
+    if exhaustive {
+        for_each_deal(&ranges, known, |hands, used, weight| {
+            let live = deck
+                .iter()
+                .filter(|&&card| !used.contains(card))
+                .cloned()
+                .collect::<Vec<Card>>();
+            for_each_combination(&live, to_deal, |runout| {
+                showdown(hands, runout, weight, &mut tally)
+            });
+        });
+
+        if tally.total_weight <= 0.0 {
+            return Err(EquityError::RangesClash);
+        }
+    } else {
+        let mut rng = match options.seed {
+            Some(seed) => ChaCha8Rng::seed_from_u64(seed),
+            None => ChaCha8Rng::from_entropy(),
+        };
+        let choosers = ranges
+            .iter()
+            .map(|range| WeightedIndex::new(range.combos().iter().map(|combo| combo.weight)))
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| EquityError::RangesClash)?;
+        let mut deck = deck;
+        let mut hands = vec![[deck[0]; 2]; ranges.len()];
+        let mut runout = Vec::with_capacity(to_deal);
+
+        for _ in 0..options.samples {
+            let used = deal_hands(&ranges, &choosers, &mut hands, &mut rng)?;
+
+            // Shuffle enough cards to have a full board left after skipping any
+            // that went to the players.
+            let (shuffled, _) = deck.partial_shuffle(&mut rng, to_deal + hole_cards);
+            runout.clear();
+            runout.extend(
+                shuffled
+                    .iter()
+                    .filter(|&&card| !used.contains(card))
+                    .take(to_deal),
+            );
+
+            showdown(&hands, &runout, 1.0, &mut tally);
+        }
+    }
+
+    Ok(tally.finish(exhaustive))
+}
+
+/// Calls `f` with every way of dealing one combo from each range without two
+/// players sharing a card, along with the cards used and the chance of it.
+fn for_each_deal(ranges: &[Range], known: CardSet, mut f: impl FnMut(&[[Card; 2]], CardSet, f64)) {
+    fn deal<F: FnMut(&[[Card; 2]], CardSet, f64)>(
+        ranges: &[Range],
+        used: CardSet,
+        weight: f64,
+        hands: &mut Vec<[Card; 2]>,
+        f: &mut F,
+    ) {
+        let range = match ranges.get(hands.len()) {
+            Some(range) => range,
+            None => return f(hands, used, weight),
+        };
+
+        for combo in range.combos() {
+            let cards = combo.card_set();
+            if combo.weight > 0.0 && (cards & used).is_empty() {
+                hands.push(combo.cards);
+                deal(ranges, used | cards, weight * combo.weight, hands, f);
+                hands.pop();
+            }
+        }
+    }
+
+    deal(ranges, known, 1.0, &mut vec![], &mut f);
+}
+
+/// Deals each player a combo from their range, chosen by weight, redealing
+/// whenever two players would share a card. Returns the cards dealt.
+fn deal_hands(
+    ranges: &[Range],
+    choosers: &[WeightedIndex<f64>],
+    hands: &mut [[Card; 2]],
+    rng: &mut ChaCha8Rng,
+) -> Result<CardSet, EquityError> {
+    'redeal: for _ in 0..MAX_REDEALS {
+        let mut used = CardSet::new();
+
+        for ((hand, range), chooser) in hands.iter_mut().zip(ranges).zip(choosers) {
+            let combo = &range.combos()[chooser.sample(rng)];
+            let cards = combo.card_set();
+            if !(cards & used).is_empty() {
+                continue 'redeal;
+            }
+
+            *hand = combo.cards;
+            used = used | cards;
+        }
+
+        return Ok(used);
+    }
+
+    Err(EquityError::RangesClash)
+}
+
+/// Puts two hole cards and a full board together into a seven card hand.
+pub(crate) fn seven_cards(hand: &[Card; 2], board: &[Card]) -> [Card; 7] {
+    [
+        hand[0], hand[1], board[0], board[1], board[2], board[3], board[4],
+    ]
+}
+
+impl fmt::Display for Equity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, player) in self.players.iter().enumerate() {
+            write!(f, "Player {}: equity {:.2}%", i + 1, player.equity * 100.0)?;
+            if !self.exhaustive {
+                write!(f, " \u{b1} {:.2}%", player.margin * 100.0)?;
+            }
+            writeln!(
+                f,
+                " (win {:.2}%, tie {:.2}%)",
+                player.win * 100.0,
+                player.tie * 100.0
+            )?;
+        }
+
+        if self.exhaustive {
+            write!(f, "Every one of {} boards evaluated.", self.boards)
+        } else {
+            write!(f, "{} boards sampled, 95% confidence.", self.boards)
+        }
+    }
+}
+
+#[cfg(test)]
+fn hole_cards(text: &str) -> [Card; 2] {
+    let cards = parse_hand(text).unwrap();
+    [cards[0], cards[1]]
+}
+
+#[test]
+fn test_equity_on_the_river_is_decided() {
+    let hands = [hole_cards("AhKh"), hole_cards("QsQd")];
+    let board = parse_hand("7c8h2dKs3c").unwrap();
+
+    let equity = calculate(&hands, &board, &[], &EquityOptions::default()).unwrap();
+
+    assert!(equity.exhaustive);
+    assert_eq!(equity.boards, 1);
+    assert_eq!(equity.players[0].win, 1.0);
+    assert_eq!(equity.players[1].equity, 0.0);
+}
+
+#[test]
+fn test_equity_on_the_flop() {
+    // AhKh needs an Ace or King, or runner runner hearts, against QsQd.
+    let hands = [hole_cards("AhKh"), hole_cards("QsQd")];
+    let board = parse_hand("7c8h2d").unwrap();
+
+    let equity = calculate(&hands, &board, &[], &EquityOptions::default()).unwrap();
+
+    assert!(equity.exhaustive);
+    assert_eq!(equity.boards, 990);
+    let total = equity.players[0].equity + equity.players[1].equity;
+    assert!((total - 1.0).abs() < 1e-9);
+    assert!(
+        (equity.players[0].equity - 0.2828).abs() < 0.0001,
+        "{}",
+        equity
+    );
+}
+
+#[test]
+fn test_equity_splits_identical_hands() {
+    let hands = [hole_cards("AhKd"), hole_cards("AsKc")];
+    let board = parse_hand("2c3d7h8s").unwrap();
+
+    let equity = calculate(&hands, &board, &[], &EquityOptions::default()).unwrap();
+
+    // Only a river club or spade can make a flush for one of them.
+    assert_eq!(equity.boards, 44);
+    assert!((equity.players[0].equity - 0.5).abs() < 1e-9);
+    assert!(equity.players[0].tie > 0.9);
+}
+
+#[test]
+fn test_equity_sampling_has_a_margin() {
+    let hands = [hole_cards("AhKh"), hole_cards("QsQd"), hole_cards("7c7d")];
+    let options = EquityOptions {
+        max_exhaustive_boards: 0,
+        samples: 20_000,
+        seed: Some(3),
+    };
+
+    let sampled = calculate(&hands, &[], &[], &options).unwrap();
+    let again = calculate(&hands, &[], &[], &options).unwrap();
+
+    assert!(!sampled.exhaustive);
+    assert_eq!(sampled, again);
+    for player in sampled.players.iter() {
+        assert!(player.margin > 0.0 && player.margin < 0.02);
+    }
+}
+
+#[test]
+fn test_hand_against_range() {
+    // Against exactly KK, AsAh has the same equity however the Kings are suited.
+    let aces = Range::from(hole_cards("AsAh"));
+    let kings = "KK".parse::<Range>().unwrap();
+    let options = EquityOptions::default();
+    let board = parse_hand("2c7d9h").unwrap();
+
+    let against_range = calculate_ranges(&[aces.clone(), kings], &board, &[], &options).unwrap();
+    let against_hand = calculate(
+        &[hole_cards("AsAh"), hole_cards("KcKd")],
+        &board,
+        &[],
+        &options,
+    )
+    .unwrap();
+
+    assert!(against_range.exhaustive);
+    assert_eq!(against_range.boards, 6 * 990);
+    assert!((against_range.players[0].equity - against_hand.players[0].equity).abs() < 0.01);
+}
+
+#[test]
+fn test_range_against_range() {
+    let ranges = [
+        "QQ+, AKs".parse::<Range>().unwrap(),
+        "JJ-99, AQs+".parse::<Range>().unwrap(),
+    ];
+    let board = parse_hand("Ks7c2dAh").unwrap();
+
+    let exhaustive = calculate_ranges(&ranges, &board, &[], &EquityOptions::default()).unwrap();
+    let sampled = calculate_ranges(
+        &ranges,
+        &board,
+        &[],
+        &EquityOptions {
+            max_exhaustive_boards: 0,
+            samples: 50_000,
+            seed: Some(7),
+        },
+    )
+    .unwrap();
+
+    assert!(exhaustive.exhaustive && !sampled.exhaustive);
+    for (exact, estimate) in exhaustive.players.iter().zip(sampled.players.iter()) {
+        assert!((exact.equity - estimate.equity).abs() < estimate.margin * 2.0);
+    }
+}
+
+#[test]
+fn test_ranges_respect_card_removal() {
+    let options = EquityOptions::default();
+
+    assert_eq!(
+        calculate_ranges(
+            &[Range::from(hole_cards("AsKs")), "AsAh".parse().unwrap()],
+            &[],
+            &[],
+            &options
+        ),
+        Err(EquityError::RangesClash)
+    );
+    assert_eq!(
+        calculate_ranges(
+            &[Range::from(hole_cards("KsKd")), "AA".parse().unwrap()],
+            &parse_hand("AsAhAd").unwrap(),
+            &parse_hand("Ac").unwrap(),
+            &options
+        ),
+        Err(EquityError::EmptyRange(1))
+    );
+}
+
+#[test]
+fn test_equity_rejects_bad_input() {
+    let options = EquityOptions::default();
+    let aces = hole_cards("AhAd");
+
+    assert_eq!(
+        calculate(&[aces], &[], &[], &options),
+        Err(EquityError::TooFewPlayers)
+    );
+    assert_eq!(
+        calculate(&[aces, hole_cards("AhKs")], &[], &[], &options),
+        Err(EquityError::DuplicateCard(Card::new(
+            Suit::Hearts,
+            Value::Ace
+        )))
+    );
+    assert_eq!(
+        calculate(
+            &[aces, hole_cards("KhKs")],
+            &parse_hand("2c3c4c5c6c7c").unwrap(),
+            &[],
+            &options
+        ),
+        Err(EquityError::BoardTooLarge(6))
+    );
+
+    // With everything else dead only four cards are left for the board.
+    let kings = hole_cards("KhKs");
+    let dead = CardSet::full()
+        .iter()
+        .filter(|card| !aces.contains(card) && !kings.contains(card))
+        .skip(4)
+        .collect::<Vec<Card>>();
+    assert_eq!(
+        calculate(&[aces, kings], &[], &dead, &options),
+        Err(EquityError::NotEnoughCards(2))
+    );
+}
@@ -0,0 +1,348 @@
+//! Table driven hand evaluation.
+//!
+//! Every five card poker hand falls into one of 7,462 distinct classes. Here each
+//! class is given a strength from 1 (7-5-4-3-2 high) up to 7462 (a royal flush),
+//! and lookup tables map any five, six or seven cards straight to the strength of
+//! the best five card hand they contain:
+//!
+//! - `flush` is indexed by the 13-bit mask of the ranks held in a single suit and
+//!   holds the best flush or straight flush those ranks make.
+//! - `ranks` is indexed by a perfect hash of how many cards of each rank are held
+//!   and holds the best hand those ranks make, ignoring suits.
+//!
+//! The tables are built once, on first use, from `HandResult::check_hand`, so the
+//! two evaluators agree by construction.
+
+use crate::card::*;
+use crate::hand_result::HandResult;
+
+use std::sync::OnceLock;
+
+const NUM_RANKS: usize = 13;
+const MAX_PER_RANK: usize = 4;
+const MIN_CARDS: usize = 5;
+const MAX_CARDS: usize = 7;
+
+struct Tables {
+    // Every distinct five card hand, weakest first. A hand's strength is its
+    // index in here plus one.
+    classes: Vec<HandResult>,
+    flush: Vec<u16>,
+    ranks: Vec<u16>,
+    // hash[i][k][q] is the number of rank patterns that come before one holding
+    // q cards of rank i, when k cards are still to be placed from rank i onwards.
+    hash: [[[u32; MAX_PER_RANK + 1]; MAX_CARDS + 1]; NUM_RANKS],
+    // Where the patterns of each hand size start in `ranks`.
+    offsets: [u32; MAX_CARDS + 1],
+}
+
+static TABLES: OnceLock<Tables> = OnceLock::new();
+
+fn tables() -> &'static Tables {
+    TABLES.get_or_init(Tables::new)
+}
+
+/// Returns the strength of the best five card hand in `hand`, which must hold
+/// five, six or seven distinct cards. A higher strength is a better hand and
+/// equal strengths tie.
+pub fn strength(hand: &[Card]) -> u16 {
+    assert!(
+        hand.len() >= MIN_CARDS && hand.len() <= MAX_CARDS,
+        "Can only evaluate hands of 5 to 7 cards, got {}.",
+        hand.len()
+    );
+
+    let mut counts = [0u8; NUM_RANKS];
+    let mut suits = [0u16; 4];
+
+    for card in hand {
+        let rank = card.value.rank();
+        counts[rank as usize] += 1;
+        suits[card.suit.index() as usize] |= 1 << rank;
+    }
+
+    tables().lookup(&counts, &suits, hand.len())
+}
+
+/// Returns the strength of the best five card hand in `cards`, which must hold
+/// five, six or seven cards.
+pub fn set_strength(cards: CardSet) -> u16 {
+    assert!(
+        cards.len() >= MIN_CARDS && cards.len() <= MAX_CARDS,
+        "Can only evaluate hands of 5 to 7 cards, got {}.",
+        cards.len()
+    );
+
+    let suits = [
+        cards.suit_mask(Suit::Clubs),
+        cards.suit_mask(Suit::Diamonds),
+        cards.suit_mask(Suit::Hearts),
+        cards.suit_mask(Suit::Spades),
+    ];
+    let mut counts = [0u8; NUM_RANKS];
+
+    for (rank, count) in counts.iter_mut().enumerate() {
+        *count = suits.iter().map(|mask| (mask >> rank) as u8 & 1).sum();
+    }
+
+    tables().lookup(&counts, &suits, cards.len())
+}
+
+/// Returns the strength of an already evaluated hand, or 0 if it was made from
+/// fewer than five cards.
+pub fn strength_of(hand_result: &HandResult) -> u16 {
+    tables()
+        .classes
+        .binary_search(hand_result)
+        .map(|i| i as u16 + 1)
+        .unwrap_or(0)
+}
+
+/// Returns the hand class with the given strength. The returned `HandResult`
+/// has no cards, only the category and ranks.
+pub(crate) fn class(strength: u16) -> &'static HandResult {
+    &tables().classes[strength as usize - 1]
+}
+
+impl Tables {
+    fn new() -> Tables {
+        // Every way of holding five cards, split by rank, with no flush.
+        let five_card_patterns = patterns(MIN_CARDS);
+        let mut classes = five_card_patterns
+            .iter()
+            .map(|counts| HandResult::check_hand(&mut pattern_cards(counts)))
+            .collect::<Vec<HandResult>>();
+
+        // Plus every set of five different ranks in a single suit.
+        for mask in 0..1u16 << NUM_RANKS {
+            if mask.count_ones() as usize == MIN_CARDS {
+                classes.push(HandResult::check_hand(&mut mask_cards(mask)));
+            }
+        }
+
+        classes.sort();
+        classes.dedup();
+
+        let strength_of =
+            |hand_result: &HandResult| classes.binary_search(hand_result).unwrap() as u16 + 1;
+
+        // Masks with more than five ranks only ever have sub-masks below them, so
+        // a single pass in order can take the best of dropping each rank.
+        let mut flush = vec![0u16; 1 << NUM_RANKS];
+
+        for mask in 0..1u16 << NUM_RANKS {
+            let count = mask.count_ones() as usize;
+            if count == MIN_CARDS {
+                flush[mask as usize] = strength_of(&HandResult::check_hand(&mut mask_cards(mask)));
+            } else if count > MIN_CARDS {
+                flush[mask as usize] = (0..NUM_RANKS)
+                    .filter(|rank| mask & (1 << rank) != 0)
+                    .map(|rank| flush[(mask & !(1 << rank)) as usize])
+                    .max()
+                    .unwrap();
+            }
+        }
+
+        let mut tables = Tables {
+            classes: vec![],
+            flush,
+            ranks: vec![],
+            hash: [[[0; MAX_PER_RANK + 1]; MAX_CARDS + 1]; NUM_RANKS],
+            offsets: [0; MAX_CARDS + 1],
+        };
+
+        // ways[l][k] is the number of ways to hold k cards across l ranks.
+        let mut ways = [[0u32; MAX_CARDS + 1]; NUM_RANKS + 1];
+        ways[0][0] = 1;
+        for l in 1..=NUM_RANKS {
+            for k in 0..=MAX_CARDS {
+                ways[l][k] = (0..=k.min(MAX_PER_RANK)).map(|q| ways[l - 1][k - q]).sum();
+            }
+        }
+
+        for i in 0..NUM_RANKS {
+            for k in 0..=MAX_CARDS {
+                for q in 1..=MAX_PER_RANK {
+                    tables.hash[i][k][q] = tables.hash[i][k][q - 1]
+                        + if q - 1 <= k {
+                            ways[NUM_RANKS - i - 1][k - (q - 1)]
+                        } else {
+                            0
+                        };
+                }
+            }
+        }
+
+        let mut size = 0;
+        for (offset, num_ways) in tables
+            .offsets
+            .iter_mut()
+            .zip(ways[NUM_RANKS].iter())
+            .skip(MIN_CARDS)
+        {
+            *offset = size;
+            size += num_ways;
+        }
+
+        let mut ranks = vec![0u16; size as usize];
+
+        for counts in five_card_patterns.iter() {
+            ranks[tables.rank_hash(counts, MIN_CARDS)] =
+                strength_of(&HandResult::check_hand(&mut pattern_cards(counts)));
+        }
+
+        // Bigger hands are as strong as the best hand left after dropping a card.
+        for n in MIN_CARDS + 1..=MAX_CARDS {
+            for counts in patterns(n) {
+                let mut best = 0;
+                for rank in 0..NUM_RANKS {
+                    if counts[rank] > 0 {
+                        let mut smaller = counts;
+                        smaller[rank] -= 1;
+                        best = best.max(ranks[tables.rank_hash(&smaller, n - 1)]);
+                    }
+                }
+                ranks[tables.rank_hash(&counts, n)] = best;
+            }
+        }
+
+        tables.ranks = ranks;
+        tables.classes = classes;
+        tables
+    }
+
+    /// Finds the best hand given the number of cards held of each rank and the
+    /// ranks held in each suit.
+    fn lookup(&self, counts: &[u8; NUM_RANKS], suits: &[u16; 4], num_cards: usize) -> u16 {
+        let mut best = self.ranks[self.rank_hash(counts, num_cards)];
+
+        for &mask in suits.iter() {
+            if mask.count_ones() as usize >= MIN_CARDS {
+                best = best.max(self.flush[mask as usize]);
+            }
+        }
+
+        best
+    }
+
+    /// Maps the number of cards held of each rank, `num_cards` in total, to a
+    /// unique index in `ranks`.
+    fn rank_hash(&self, counts: &[u8; NUM_RANKS], num_cards: usize) -> usize {
+        let mut index = self.offsets[num_cards];
+        let mut remaining = num_cards;
+
+        for (rank, &count) in counts.iter().enumerate() {
+            index += self.hash[rank][remaining][count as usize];
+            remaining -= count as usize;
+        }
+
+        index as usize
+    }
+}
+
+/// Lists every way of holding `num_cards` cards, as the number held of each rank.
+fn patterns(num_cards: usize) -> Vec<[u8; NUM_RANKS]> {
+    fn fill(
+        rank: usize,
+        remaining: usize,
+        counts: &mut [u8; NUM_RANKS],
+        out: &mut Vec<[u8; NUM_RANKS]>,
+    ) {
+        if rank == NUM_RANKS {
+            if remaining == 0 {
+                out.push(*counts);
+            }
+            return;
+        }
+
+        for count in 0..=remaining.min(MAX_PER_RANK) {
+            counts[rank] = count as u8;
+            fill(rank + 1, remaining - count, counts, out);
+        }
+        counts[rank] = 0;
+    }
+
+    let mut out = vec![];
+    fill(0, num_cards, &mut [0; NUM_RANKS], &mut out);
+    out
+}
+
+/// Builds the cards for a rank pattern, cycling through the suits so that cards
+/// of the same rank differ in suit and no five cards share a suit.
+fn pattern_cards(counts: &[u8; NUM_RANKS]) -> Vec<Card> {
+    let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+    let mut cards = vec![];
+    for (rank, &count) in counts.iter().enumerate() {
+        for _ in 0..count {
+            cards.push(Card::new(
+                suits[cards.len() % 4],
+                Value::from_rank(rank as u8),
+            ));
+        }
+    }
+    cards
+}
+
+/// Builds one Club for every rank set in `mask`.
+fn mask_cards(mask: u16) -> Vec<Card> {
+    (0..NUM_RANKS)
+        .filter(|rank| mask & (1 << rank) != 0)
+        .map(|rank| Card::new(Suit::Clubs, Value::from_rank(rank as u8)))
+        .collect()
+}
+
+#[test]
+fn test_number_of_hand_classes() {
+    assert_eq!(tables().classes.len(), 7462);
+    assert_eq!(tables().ranks.len(), 6175 + 18395 + 49205);
+}
+
+#[test]
+fn test_strength_matches_check_hand() {
+    use rand::seq::SliceRandom;
+    use rand::thread_rng;
+    use strum::IntoEnumIterator;
+
+    let mut rng = thread_rng();
+    let mut deck = vec![];
+
+    for suit in Suit::iter() {
+        for value in Value::iter() {
+            deck.push(Card::new(suit, value));
+        }
+    }
+
+    for i in 0..30_000 {
+        deck.shuffle(&mut rng);
+        let hand = &deck[..MIN_CARDS + i % 3];
+
+        let expected = HandResult::check_hand(&mut hand.to_vec());
+
+        assert_eq!(strength(hand), strength_of(&expected), "{:?}", hand);
+        assert_eq!(set_strength(hand.into()), strength(hand), "{:?}", hand);
+        assert_eq!(*class(strength(hand)), expected, "{:?}", hand);
+    }
+}
+
+#[test]
+fn test_strength_extremes() {
+    let worst = [
+        Card::new(Suit::Clubs, Value::Seven),
+        Card::new(Suit::Diamonds, Value::Five),
+        Card::new(Suit::Hearts, Value::Four),
+        Card::new(Suit::Spades, Value::Three),
+        Card::new(Suit::Clubs, Value::Two),
+    ];
+    let royal = [
+        Card::new(Suit::Spades, Value::Ace),
+        Card::new(Suit::Spades, Value::King),
+        Card::new(Suit::Spades, Value::Queen),
+        Card::new(Suit::Spades, Value::Jack),
+        Card::new(Suit::Spades, Value::Ten),
+        Card::new(Suit::Hearts, Value::Two),
+        Card::new(Suit::Diamonds, Value::Two),
+    ];
+
+    assert_eq!(strength(&worst), 1);
+    assert_eq!(strength(&royal), 7462);
+}
@@ -1,12 +1,15 @@
 use crate::card::*;
+use crate::evaluator;
 
 use self::ResultName::*;
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::fmt;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 enum ResultName {
+    // Only possible with wild cards.
+    FiveOfAKind,
     StraightFlush,
     FourOfAKind,
     FullHouse,
@@ -18,64 +21,154 @@ enum ResultName {
     HighCard,
 }
 
+/// How hand categories are ordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Ranking {
+    #[default]
+    Standard,
+    /// Short deck (6+) Hold'em, played without the Twos to Fives. A Flush beats
+    /// a Full House, the lowest straight is A-6-7-8-9, and some rooms also have
+    /// Three Of A Kind beat a Straight.
+    ShortDeck { trips_beat_straight: bool },
+}
+
+impl Ranking {
+    // The value an Ace takes when it plays at the bottom of a straight.
+    fn low_ace(&self) -> u32 {
+        match self {
+            Ranking::Standard => 1,
+            Ranking::ShortDeck { .. } => 5,
+        }
+    }
+}
+
 #[derive(Debug, Eq)]
 pub struct HandResult {
     result: ResultName,
-    highest: Card,
+    // The values that decide the hand within its category, most significant
+    // first, e.g. [pair, kicker, kicker, kicker] or [trips, pair].
+    ranks: Vec<u32>,
+    // The cards making up the hand, in the same order as `ranks`.
+    cards: Vec<Card>,
+    ranking: Ranking,
 }
 
 impl HandResult {
     pub fn check_hand(hand: &mut [Card]) -> HandResult {
-        if hand.len() == 0 {
-            panic!("Panicked due to checking an empty hand.");
-        }
+        Self::check_hand_with(hand, Ranking::Standard)
+    }
 
-        let flush_check = Self::check_flush(hand);
-        if flush_check.result == StraightFlush {
-            return flush_check;
-        }
+    /// Like `check_hand`, with the categories ordered by `ranking`.
+    pub fn check_hand_with(hand: &mut [Card], ranking: Ranking) -> HandResult {
+        let mut hand_result = Self::classify(hand, ranking);
+        hand_result.cards =
+            Self::select_cards(hand, &hand_result.result, &hand_result.ranks, ranking);
+        hand_result
+    }
 
-        let of_a_kind_check = Self::check_of_a_kind(hand);
-        if of_a_kind_check.result == FourOfAKind {
-            return of_a_kind_check;
+    /// Evaluates a hand of five to seven cards using the lookup tables in
+    /// `evaluator`. Gives the same result as `check_hand`, only much faster.
+    /// Hands of any other size fall back to `check_hand`.
+    pub fn evaluate(hand: &[Card]) -> HandResult {
+        if hand.len() < 5 || hand.len() > 7 {
+            return Self::check_hand(&mut hand.to_vec());
         }
 
-        if of_a_kind_check.result == FullHouse {
-            return of_a_kind_check;
-        }
+        let class = evaluator::class(evaluator::strength(hand));
 
-        if flush_check.result == Flush {
-            return flush_check;
+        HandResult {
+            result: class.result,
+            ranks: class.ranks.clone(),
+            cards: Self::select_cards(hand, &class.result, &class.ranks, Ranking::Standard),
+            ranking: Ranking::Standard,
         }
+    }
 
-        let straight_check = Self::check_straight(hand);
-        if straight_check.result == Straight {
-            return straight_check;
+    /// Where this hand sits among the 7,462 distinct five card hands, from 1 for
+    /// the weakest up to 7462 for a royal flush. Hands made from fewer than five
+    /// cards, Five Of A Kind, or hands ranked other than the standard way have a
+    /// strength of 0.
+    pub fn strength(&self) -> u16 {
+        if self.ranking != Ranking::Standard {
+            return 0;
         }
+        evaluator::strength_of(self)
+    }
 
-        if of_a_kind_check.result == ThreeOfAKind {
-            return of_a_kind_check;
-        }
+    /// Returns the five cards forming the best hand that can be made from `hand`.
+    /// Hands of fewer than five cards return every card that counts towards the
+    /// hand.
+    pub fn best_five(hand: &[Card]) -> Vec<Card> {
+        Self::check_hand(&mut hand.to_vec()).cards
+    }
 
-        if of_a_kind_check.result == TwoPair {
-            return of_a_kind_check;
-        }
+    /// The category of this hand, from 0 for High Card up to 8 for a Straight
+    /// Flush, or 9 for Five Of A Kind, in the order given by its ranking.
+    pub fn category(&self) -> u32 {
+        self.result.rank(self.ranking)
+    }
+
+    /// The cards making up this hand, most significant first. For example a full
+    /// house lists the three of a kind before the pair, and an Ace-low straight
+    /// lists the Five first and the Ace last.
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
 
-        if of_a_kind_check.result == Pair {
-            return of_a_kind_check;
+    /// The cards that make the category, leaving out any kickers: the pair of a
+    /// Pair, both pairs of Two Pair, the top card of High Card, and all five
+    /// of a Straight, Flush or Full House.
+    pub fn made_cards(&self) -> &[Card] {
+        let made = match self.result {
+            HighCard => 1,
+            Pair => 2,
+            ThreeOfAKind => 3,
+            TwoPair | FourOfAKind => 4,
+            _ => 5,
+        };
+        &self.cards[..made.min(self.cards.len())]
+    }
+
+    /// Five Of A Kind, made with wild cards. `cards` holds the five cards of the
+    /// same value, with each wild card given as the card it stands for.
+    pub(crate) fn five_of_a_kind(cards: Vec<Card>) -> HandResult {
+        HandResult {
+            result: FiveOfAKind,
+            ranks: vec![cards[0].value.value()],
+            cards,
+            ranking: Ranking::Standard,
         }
+    }
 
-        hand.sort_by(|a, b| a.value.value().cmp(&b.value.value()));
+    fn classify(hand: &mut [Card], ranking: Ranking) -> HandResult {
+        if hand.is_empty() {
+            panic!("Panicked due to checking an empty hand.");
+        }
 
-        return HandResult {
+        let high_card = HandResult {
             result: HighCard,
-            highest: *hand.last().unwrap(),
+            ranks: Self::kickers(hand, &[], 5),
+            cards: vec![],
+            ranking,
         };
+
+        // Each check finds the best hand of its own kind, so the best of those
+        // under the ranking is the hand.
+        vec![
+            Self::check_flush(hand, ranking),
+            Self::check_of_a_kind(hand),
+            Self::check_straight(hand, ranking),
+            high_card,
+        ]
+        .into_iter()
+        .map(|check| HandResult { ranking, ..check })
+        .max()
+        .unwrap()
     }
 
-    fn check_flush(hand: &mut [Card]) -> HandResult {
+    fn check_flush(hand: &mut [Card], ranking: Ranking) -> HandResult {
         let mut result = HighCard;
-        let mut highest = hand[0];
+        let mut ranks = vec![];
 
         // Make a hashmap to keep track of the number of cards of the same suit.
         let mut card_map = HashMap::<Suit, u32>::new();
@@ -95,122 +188,205 @@ impl HandResult {
                     .cloned()
                     .collect::<Vec<Card>>();
 
-                let test = Self::check_straight(&mut flush_cards);
+                let test = Self::check_straight(&mut flush_cards, ranking);
 
                 if test.result == Straight {
                     result = StraightFlush;
+                    ranks = test.ranks;
                 } else {
                     result = Flush;
+                    ranks = Self::kickers(&flush_cards, &[], 5);
                 }
-
-                highest = *flush_cards.last().unwrap();
             }
         }
 
         HandResult {
-            result: result,
-            highest: highest,
+            result,
+            ranks,
+            cards: vec![],
+            ranking,
         }
     }
 
     fn check_of_a_kind(hand: &mut [Card]) -> HandResult {
         let mut result = HighCard;
-        let mut highest = hand[0];
+        let mut ranks = vec![];
 
         // Make a hashmap to keep track of the number of cards of the same value.
-        let mut card_map = HashMap::<Value, Vec<Card>>::new();
+        let mut card_map = HashMap::<u32, usize>::new();
 
-        // For each card
         for card in hand.iter() {
-            // If this card's suit is already in the map, increment its count and
-            // re-insert
-            card_map.entry(card.value).or_insert(vec![]).push(*card);
+            *card_map.entry(card.value.value()).or_insert(0) += 1;
         }
 
-        let mut final_cards = Vec::<Card>::new();
-
-        for (v, n) in card_map.into_iter() {
-            if n.len() == 4 {
-                return HandResult {
-                    result: FourOfAKind,
-                    highest: hand
-                        .iter()
-                        .filter(|&c| c.value == v)
-                        .cloned()
-                        .collect::<Vec<Card>>()[0],
-                };
+        // (count, value) of every value appearing more than once, biggest group
+        // first with ties broken by the higher value. This puts the trips ahead
+        // of the pairs and the higher of two trips or pairs first, whatever mix
+        // of groups the hand holds.
+        let mut groups = card_map
+            .into_iter()
+            .filter(|&(_, n)| n > 1)
+            .map(|(v, n)| (n, v))
+            .collect::<Vec<(usize, u32)>>();
+        groups.sort_by(|a, b| b.cmp(a));
+
+        match groups.as_slice() {
+            [(4, quads), ..] => {
+                result = FourOfAKind;
+                ranks = vec![*quads];
+                // Any remaining trips or pair only count as a single kicker.
+                ranks.extend(Self::kickers(hand, &[*quads], 1));
             }
-
-            if n.len() > 1 {
-                final_cards.extend(n);
+            [(3, trips), (_, pair), ..] => {
+                // A second set of trips plays as the pair of the full house.
+                result = FullHouse;
+                ranks = vec![*trips, *pair];
             }
-        }
-
-        final_cards.sort_by(|a, b| a.value.value().cmp(&b.value.value()));
-
-        if final_cards.len() == 5 || final_cards.len() == 7 {
-            result = FullHouse;
-            highest = *final_cards.last().unwrap();
-        } else if final_cards.len() == 4 || final_cards.len() == 6 {
-            result = TwoPair;
-            highest = *final_cards.last().unwrap();
-        } else if final_cards.len() == 3 {
-            result = ThreeOfAKind;
-            highest = *final_cards.last().unwrap();
-        } else if final_cards.len() == 2 {
-            result = Pair;
-            highest = *final_cards.last().unwrap();
+            [(3, trips)] => {
+                result = ThreeOfAKind;
+                ranks = vec![*trips];
+                ranks.extend(Self::kickers(hand, &[*trips], 2));
+            }
+            [(2, high), (2, low), ..] => {
+                // With three pairs the lowest pair is only worth a kicker.
+                result = TwoPair;
+                ranks = vec![*high, *low];
+                ranks.extend(Self::kickers(hand, &[*high, *low], 1));
+            }
+            [(2, pair)] => {
+                result = Pair;
+                ranks = vec![*pair];
+                ranks.extend(Self::kickers(hand, &[*pair], 3));
+            }
+            _ => {}
         }
 
         HandResult {
-            result: result,
-            highest: highest,
+            result,
+            ranks,
+            cards: vec![],
+            ranking: Ranking::Standard,
         }
     }
 
-    fn check_straight(hand: &mut [Card]) -> HandResult {
+    fn check_straight(hand: &mut [Card], ranking: Ranking) -> HandResult {
         let mut result = HighCard;
+        let mut ranks = vec![];
 
-        hand.sort_by(|a, b| a.value.value().cmp(&b.value.value()));
-
-        let mut highest_card = hand[0];
+        hand.sort_by_key(|c| c.value.value());
 
-        let mut in_a_row = 1;
-        let mut prev_value = hand[0].value.value();
+        // Only distinct values matter, so a paired card can't break up a run.
+        let mut values = hand.iter().map(|c| c.value.value()).collect::<Vec<u32>>();
+        values.dedup();
 
-        if hand.last().unwrap().value == Value::Ace && highest_card.value == Value::Two {
-            in_a_row = 2;
+        // An Ace also plays at the bottom, making the Five-high wheel, or A-6-7-8-9
+        // in a short deck.
+        if values.last() == Some(&Value::Ace.value()) {
+            values.insert(0, ranking.low_ace());
         }
 
-        for (i, card) in hand.iter().enumerate() {
-            if i == 0 {
-                continue;
-            }
+        let mut in_a_row = 1;
 
-            if card.value.value() == prev_value + 1 {
-                in_a_row = in_a_row + 1;
+        for pair in values.windows(2) {
+            if pair[1] == pair[0] + 1 {
+                in_a_row += 1;
             } else {
                 in_a_row = 1;
             }
 
             if in_a_row >= 5 {
                 result = Straight;
-                highest_card = *card;
+                ranks = vec![pair[1]];
             }
-
-            prev_value = card.value.value();
         }
 
         HandResult {
-            result: result,
-            highest: highest_card,
+            result,
+            ranks,
+            cards: vec![],
+            ranking,
+        }
+    }
+
+    /// Picks the cards out of `hand` that match the category and ranks found by
+    /// `classify`.
+    fn select_cards(
+        hand: &[Card],
+        result: &ResultName,
+        ranks: &[u32],
+        ranking: Ranking,
+    ) -> Vec<Card> {
+        // Straights only store their top card, so expand them to all five values,
+        // with an Ace at the bottom of a wheel.
+        let needed = match *result {
+            StraightFlush | Straight => (0..5)
+                .map(|i| {
+                    let value = ranks[0] - i;
+                    (
+                        if value == ranking.low_ace() {
+                            14
+                        } else {
+                            value
+                        },
+                        1,
+                    )
+                })
+                .collect::<Vec<(u32, usize)>>(),
+            _ => {
+                let counts: &[usize] = match *result {
+                    FourOfAKind => &[4, 1],
+                    FullHouse => &[3, 2],
+                    ThreeOfAKind => &[3, 1, 1],
+                    TwoPair => &[2, 2, 1],
+                    Pair => &[2, 1, 1, 1],
+                    _ => &[1, 1, 1, 1, 1],
+                };
+                ranks.iter().cloned().zip(counts.iter().cloned()).collect()
+            }
+        };
+
+        // Flushes may only use cards of the flush suit.
+        let flush_suit = match *result {
+            StraightFlush | Flush => hand
+                .iter()
+                .map(|c| c.suit)
+                .find(|&s| hand.iter().filter(|c| c.suit == s).count() >= 5),
+            _ => None,
+        };
+
+        let mut cards = Vec::<Card>::new();
+
+        for (value, count) in needed {
+            cards.extend(
+                hand.iter()
+                    .filter(|c| c.value.value() == value)
+                    .filter(|c| flush_suit.is_none_or(|s| c.suit == s))
+                    .take(count),
+            );
         }
+
+        cards
+    }
+
+    /// Returns the `count` highest values in `hand`, highest first, skipping any
+    /// value listed in `exclude`.
+    fn kickers(hand: &[Card], exclude: &[u32], count: usize) -> Vec<u32> {
+        let mut values = hand
+            .iter()
+            .map(|c| c.value.value())
+            .filter(|v| !exclude.contains(v))
+            .collect::<Vec<u32>>();
+
+        values.sort_by(|a, b| b.cmp(a));
+        values.truncate(count);
+        values
     }
 }
 
 impl ResultName {
     fn value(&self) -> u32 {
         match *self {
+            FiveOfAKind => 9,
             StraightFlush => 8,
             FourOfAKind => 7,
             FullHouse => 6,
@@ -222,17 +398,41 @@ impl ResultName {
             HighCard => 0,
         }
     }
+
+    /// Where the category sits under `ranking`, higher beating lower.
+    fn rank(&self, ranking: Ranking) -> u32 {
+        match (ranking, self) {
+            (Ranking::ShortDeck { .. }, Flush) => 6,
+            (Ranking::ShortDeck { .. }, FullHouse) => 5,
+            (
+                Ranking::ShortDeck {
+                    trips_beat_straight: true,
+                },
+                ThreeOfAKind,
+            ) => 4,
+            (
+                Ranking::ShortDeck {
+                    trips_beat_straight: true,
+                },
+                Straight,
+            ) => 3,
+            _ => self.value(),
+        }
+    }
+}
+
+impl HandResult {
+    // What hands are ordered and compared equal by: the category under the
+    // hand's ranking, then the values deciding it within the category.
+    fn key(&self) -> (u32, &[u32]) {
+        (self.result.rank(self.ranking), &self.ranks)
+    }
 }
 
 impl Ord for HandResult {
     fn cmp(&self, other: &Self) -> Ordering {
-        if self.result.value() > other.result.value() {
-            Ordering::Greater
-        } else if self.result.value() < other.result.value() {
-            Ordering::Less
-        } else {
-            self.highest.cmp(&other.highest)
-        }
+        debug_assert_eq!(self.ranking, other.ranking, "Hands ranked different ways.");
+        self.key().cmp(&other.key())
     }
 }
 
@@ -244,13 +444,17 @@ impl PartialOrd for HandResult {
 
 impl PartialEq for HandResult {
     fn eq(&self, other: &Self) -> bool {
-        self.result == other.result && self.highest == other.highest
+        debug_assert_eq!(self.ranking, other.ranking, "Hands ranked different ways.");
+        self.key() == other.key()
     }
 }
 
 impl fmt::Display for HandResult {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.result {
+            FiveOfAKind => {
+                write!(f, "5 Of A Kind")?;
+            }
             StraightFlush => {
                 write!(f, "Straight Flush")?;
             }
@@ -299,7 +503,7 @@ fn test_check_straight_flush() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, StraightFlush);
-    assert_eq!(test_result.highest, Card::new(Suit::Hearts, Value::Six));
+    assert_eq!(test_result.ranks, vec![6]);
 }
 
 #[test]
@@ -317,7 +521,7 @@ fn test_check_non_straight_flush() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, Flush);
-    assert_eq!(test_result.highest, Card::new(Suit::Hearts, Value::Ten));
+    assert_eq!(test_result.ranks, vec![10, 6, 5, 4, 2]);
 }
 
 #[test]
@@ -335,7 +539,7 @@ fn test_check_straight_flush_with_ace_low() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, StraightFlush);
-    assert_eq!(test_result.highest, Card::new(Suit::Hearts, Value::Ace));
+    assert_eq!(test_result.ranks, vec![5]);
 }
 
 #[test]
@@ -353,7 +557,7 @@ fn test_check_straight_flush_with_ace_high() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, StraightFlush);
-    assert_eq!(test_result.highest, Card::new(Suit::Hearts, Value::Ace));
+    assert_eq!(test_result.ranks, vec![14]);
 }
 
 #[test]
@@ -371,7 +575,7 @@ fn test_check_four_of_a_kind() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, FourOfAKind);
-    assert_eq!(test_result.highest, Card::new(Suit::Hearts, Value::Ace));
+    assert_eq!(test_result.ranks, vec![14, 10]);
 }
 
 #[test]
@@ -389,7 +593,7 @@ fn test_check_full_house() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, FullHouse);
-    assert_eq!(test_result.highest, Card::new(Suit::Spades, Value::Ace));
+    assert_eq!(test_result.ranks, vec![14, 2]);
 }
 
 #[test]
@@ -407,7 +611,7 @@ fn test_check_flush() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, Flush);
-    assert_eq!(test_result.highest, Card::new(Suit::Diamonds, Value::Ace));
+    assert_eq!(test_result.ranks, vec![14, 13, 12, 10, 7]);
 }
 
 #[test]
@@ -425,7 +629,7 @@ fn test_check_straight() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, Straight);
-    assert_eq!(test_result.highest, Card::new(Suit::Diamonds, Value::Seven));
+    assert_eq!(test_result.ranks, vec![7]);
 }
 
 #[test]
@@ -443,7 +647,7 @@ fn test_three_of_a_kind() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, ThreeOfAKind);
-    assert_eq!(test_result.highest, Card::new(Suit::Spades, Value::Ten));
+    assert_eq!(test_result.ranks, vec![10, 14, 12]);
 }
 
 #[test]
@@ -461,7 +665,7 @@ fn test_two_pair() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, TwoPair);
-    assert_eq!(test_result.highest, Card::new(Suit::Spades, Value::Ace));
+    assert_eq!(test_result.ranks, vec![14, 10, 12]);
 }
 
 #[test]
@@ -479,7 +683,7 @@ fn test_pair() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, Pair);
-    assert_eq!(test_result.highest, Card::new(Suit::Clubs, Value::Ten));
+    assert_eq!(test_result.ranks, vec![10, 14, 12, 7]);
 }
 
 #[test]
@@ -497,5 +701,620 @@ fn test_high_card() {
     let test_result = HandResult::check_hand(&mut cards);
 
     assert_eq!(test_result.result, HighCard);
-    assert_eq!(test_result.highest, Card::new(Suit::Spades, Value::Ace));
+    assert_eq!(test_result.ranks, vec![14, 12, 10, 7, 5]);
+}
+
+#[test]
+fn test_pair_kicker_decides() {
+    let mut ace_kicker = vec![
+        Card::new(Suit::Diamonds, Value::Ten),
+        Card::new(Suit::Clubs, Value::Ten),
+        Card::new(Suit::Spades, Value::Ace),
+        Card::new(Suit::Hearts, Value::Seven),
+        Card::new(Suit::Diamonds, Value::Four),
+        Card::new(Suit::Spades, Value::Two),
+        Card::new(Suit::Clubs, Value::Queen),
+    ];
+    let mut king_kicker = vec![
+        Card::new(Suit::Hearts, Value::Ten),
+        Card::new(Suit::Spades, Value::Ten),
+        Card::new(Suit::Spades, Value::King),
+        Card::new(Suit::Hearts, Value::Seven),
+        Card::new(Suit::Diamonds, Value::Four),
+        Card::new(Suit::Spades, Value::Two),
+        Card::new(Suit::Clubs, Value::Queen),
+    ];
+
+    let ace_result = HandResult::check_hand(&mut ace_kicker);
+    let king_result = HandResult::check_hand(&mut king_kicker);
+
+    assert!(ace_result > king_result);
+}
+
+#[test]
+fn test_two_pair_kicker_decides() {
+    let mut queen_kicker = vec![
+        Card::new(Suit::Diamonds, Value::Ten),
+        Card::new(Suit::Clubs, Value::Ten),
+        Card::new(Suit::Spades, Value::Ace),
+        Card::new(Suit::Hearts, Value::Ace),
+        Card::new(Suit::Diamonds, Value::Four),
+        Card::new(Suit::Spades, Value::Two),
+        Card::new(Suit::Clubs, Value::Queen),
+    ];
+    let mut jack_kicker = vec![
+        Card::new(Suit::Diamonds, Value::Ten),
+        Card::new(Suit::Clubs, Value::Ten),
+        Card::new(Suit::Spades, Value::Ace),
+        Card::new(Suit::Hearts, Value::Ace),
+        Card::new(Suit::Diamonds, Value::Four),
+        Card::new(Suit::Spades, Value::Two),
+        Card::new(Suit::Clubs, Value::Jack),
+    ];
+
+    let queen_result = HandResult::check_hand(&mut queen_kicker);
+    let jack_result = HandResult::check_hand(&mut jack_kicker);
+
+    assert_eq!(queen_result.ranks, vec![14, 10, 12]);
+    assert!(queen_result > jack_result);
+}
+
+#[test]
+fn test_flush_compares_every_card() {
+    let mut higher = vec![
+        Card::new(Suit::Hearts, Value::Ace),
+        Card::new(Suit::Hearts, Value::Ten),
+        Card::new(Suit::Hearts, Value::Eight),
+        Card::new(Suit::Hearts, Value::Six),
+        Card::new(Suit::Hearts, Value::Three),
+    ];
+    let mut lower = vec![
+        Card::new(Suit::Spades, Value::Ace),
+        Card::new(Suit::Spades, Value::Ten),
+        Card::new(Suit::Spades, Value::Eight),
+        Card::new(Suit::Spades, Value::Six),
+        Card::new(Suit::Spades, Value::Two),
+    ];
+
+    let higher_result = HandResult::check_hand(&mut higher);
+    let lower_result = HandResult::check_hand(&mut lower);
+
+    assert!(higher_result > lower_result);
+}
+
+#[test]
+fn test_identical_ranks_are_equal() {
+    let mut first = vec![
+        Card::new(Suit::Hearts, Value::King),
+        Card::new(Suit::Spades, Value::King),
+        Card::new(Suit::Clubs, Value::Nine),
+        Card::new(Suit::Diamonds, Value::Seven),
+        Card::new(Suit::Hearts, Value::Three),
+    ];
+    let mut second = vec![
+        Card::new(Suit::Diamonds, Value::King),
+        Card::new(Suit::Clubs, Value::King),
+        Card::new(Suit::Spades, Value::Nine),
+        Card::new(Suit::Hearts, Value::Seven),
+        Card::new(Suit::Spades, Value::Three),
+    ];
+
+    let first_result = HandResult::check_hand(&mut first);
+    let second_result = HandResult::check_hand(&mut second);
+
+    assert_eq!(first_result, second_result);
+    assert_eq!(first_result.cmp(&second_result), Ordering::Equal);
+}
+
+#[test]
+fn test_best_five_full_house_picks_highest_pair() {
+    let cards = vec![
+        Card::new(Suit::Hearts, Value::Two),
+        Card::new(Suit::Diamonds, Value::Nine),
+        Card::new(Suit::Spades, Value::Nine),
+        Card::new(Suit::Clubs, Value::Nine),
+        Card::new(Suit::Hearts, Value::King),
+        Card::new(Suit::Diamonds, Value::Two),
+        Card::new(Suit::Spades, Value::King),
+    ];
+
+    let best = HandResult::best_five(&cards);
+
+    assert_eq!(
+        best,
+        vec![
+            Card::new(Suit::Diamonds, Value::Nine),
+            Card::new(Suit::Spades, Value::Nine),
+            Card::new(Suit::Clubs, Value::Nine),
+            Card::new(Suit::Hearts, Value::King),
+            Card::new(Suit::Spades, Value::King),
+        ]
+    );
+}
+
+#[test]
+fn test_best_five_flush_ignores_other_suits() {
+    let cards = vec![
+        Card::new(Suit::Diamonds, Value::Ten),
+        Card::new(Suit::Diamonds, Value::Ace),
+        Card::new(Suit::Spades, Value::Three),
+        Card::new(Suit::Diamonds, Value::Seven),
+        Card::new(Suit::Hearts, Value::Ace),
+        Card::new(Suit::Diamonds, Value::King),
+        Card::new(Suit::Diamonds, Value::Queen),
+    ];
+
+    let best = HandResult::best_five(&cards);
+
+    assert_eq!(
+        best,
+        vec![
+            Card::new(Suit::Diamonds, Value::Ace),
+            Card::new(Suit::Diamonds, Value::King),
+            Card::new(Suit::Diamonds, Value::Queen),
+            Card::new(Suit::Diamonds, Value::Ten),
+            Card::new(Suit::Diamonds, Value::Seven),
+        ]
+    );
+}
+
+#[test]
+fn test_best_five_wheel_puts_ace_last() {
+    let mut cards = vec![
+        Card::new(Suit::Hearts, Value::Ace),
+        Card::new(Suit::Hearts, Value::Two),
+        Card::new(Suit::Hearts, Value::Three),
+        Card::new(Suit::Hearts, Value::Four),
+        Card::new(Suit::Hearts, Value::Five),
+        Card::new(Suit::Diamonds, Value::Two),
+        Card::new(Suit::Spades, Value::Two),
+    ];
+
+    let test_result = HandResult::check_hand(&mut cards);
+
+    assert_eq!(
+        test_result.cards(),
+        &[
+            Card::new(Suit::Hearts, Value::Five),
+            Card::new(Suit::Hearts, Value::Four),
+            Card::new(Suit::Hearts, Value::Three),
+            Card::new(Suit::Hearts, Value::Two),
+            Card::new(Suit::Hearts, Value::Ace),
+        ]
+    );
+}
+
+#[test]
+fn test_best_five_pair_with_kickers() {
+    let cards = vec![
+        Card::new(Suit::Diamonds, Value::Ten),
+        Card::new(Suit::Spades, Value::Ace),
+        Card::new(Suit::Clubs, Value::Ten),
+        Card::new(Suit::Hearts, Value::Seven),
+        Card::new(Suit::Diamonds, Value::Four),
+        Card::new(Suit::Spades, Value::Two),
+        Card::new(Suit::Clubs, Value::Queen),
+    ];
+
+    let best = HandResult::best_five(&cards);
+
+    assert_eq!(
+        best,
+        vec![
+            Card::new(Suit::Diamonds, Value::Ten),
+            Card::new(Suit::Clubs, Value::Ten),
+            Card::new(Suit::Spades, Value::Ace),
+            Card::new(Suit::Clubs, Value::Queen),
+            Card::new(Suit::Hearts, Value::Seven),
+        ]
+    );
+}
+
+/// Builds a hand holding the given values, dealing repeated values in different
+/// suits and spreading the suits so that no flush can be made.
+#[cfg(test)]
+fn rainbow_hand(values: &[Value]) -> Vec<Card> {
+    let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+
+    values
+        .iter()
+        .enumerate()
+        .map(|(i, value)| {
+            let first = values.iter().position(|v| v == value).unwrap();
+            let copies = values[..i].iter().filter(|&v| v == value).count();
+            Card::new(suits[(first + copies) % 4], *value)
+        })
+        .collect()
+}
+
+#[test]
+fn test_of_a_kind_groupings() {
+    use Value::*;
+
+    let table: Vec<(&str, Vec<Value>, ResultName, Vec<u32>)> = vec![
+        (
+            "4+3",
+            vec![Five, Five, Five, Five, Jack, Jack, Jack],
+            FourOfAKind,
+            vec![5, 11],
+        ),
+        (
+            "4+2+1",
+            vec![Five, Five, Five, Five, Jack, Jack, Ace],
+            FourOfAKind,
+            vec![5, 14],
+        ),
+        (
+            "4+1+1+1",
+            vec![King, King, King, King, Two, Seven, Nine],
+            FourOfAKind,
+            vec![13, 9],
+        ),
+        (
+            "3+3+1",
+            vec![Nine, Nine, Nine, King, King, King, Two],
+            FullHouse,
+            vec![13, 9],
+        ),
+        (
+            "3+3",
+            vec![Four, Four, Four, Jack, Jack, Jack],
+            FullHouse,
+            vec![11, 4],
+        ),
+        (
+            "3+2+2",
+            vec![Nine, Nine, Nine, King, King, Two, Two],
+            FullHouse,
+            vec![9, 13],
+        ),
+        (
+            "3+2+2 low trips",
+            vec![Two, Two, Two, King, King, Queen, Queen],
+            FullHouse,
+            vec![2, 13],
+        ),
+        (
+            "3+2+1+1",
+            vec![Seven, Seven, Seven, Queen, Queen, Ace, Three],
+            FullHouse,
+            vec![7, 12],
+        ),
+        (
+            "3+2",
+            vec![Three, Three, Three, Eight, Eight],
+            FullHouse,
+            vec![3, 8],
+        ),
+        (
+            "3+1+1+1+1",
+            vec![Ten, Ten, Ten, Two, Four, Seven, King],
+            ThreeOfAKind,
+            vec![10, 13, 7],
+        ),
+        (
+            "2+2+2+1",
+            vec![Eight, Eight, Six, Six, Three, Three, King],
+            TwoPair,
+            vec![8, 6, 13],
+        ),
+        (
+            "2+2+2+1 pair kicker",
+            vec![Eight, Eight, Six, Six, Queen, Queen, Two],
+            TwoPair,
+            vec![12, 8, 6],
+        ),
+        (
+            "2+2+2",
+            vec![Jack, Jack, Five, Five, Three, Three],
+            TwoPair,
+            vec![11, 5, 3],
+        ),
+        (
+            "2+2+1+1+1",
+            vec![Ace, Ace, Nine, Nine, Four, Six, Jack],
+            TwoPair,
+            vec![14, 9, 11],
+        ),
+        (
+            "2+2+1",
+            vec![King, King, Four, Four, Nine],
+            TwoPair,
+            vec![13, 4, 9],
+        ),
+        (
+            "2+1+1+1+1+1",
+            vec![Three, Three, Ace, Jack, Nine, Seven, Five],
+            Pair,
+            vec![3, 14, 11, 9],
+        ),
+        (
+            "1+1+1+1+1+1+1",
+            vec![Ace, Jack, Nine, Seven, Five, Three, Two],
+            HighCard,
+            vec![14, 11, 9, 7, 5],
+        ),
+    ];
+
+    for (name, values, result, ranks) in table {
+        let mut cards = rainbow_hand(&values);
+
+        let test_result = HandResult::check_hand(&mut cards);
+
+        assert_eq!(test_result.result, result, "{}", name);
+        assert_eq!(test_result.ranks, ranks, "{}", name);
+        assert_eq!(test_result.cards().len(), 5, "{}", name);
+    }
+}
+
+#[test]
+fn test_straight_with_paired_card() {
+    let mut cards = vec![
+        Card::new(Suit::Hearts, Value::Three),
+        Card::new(Suit::Diamonds, Value::Four),
+        Card::new(Suit::Spades, Value::Four),
+        Card::new(Suit::Clubs, Value::Five),
+        Card::new(Suit::Hearts, Value::Six),
+        Card::new(Suit::Diamonds, Value::Seven),
+        Card::new(Suit::Spades, Value::King),
+    ];
+
+    let test_result = HandResult::check_hand(&mut cards);
+
+    assert_eq!(test_result.result, Straight);
+    assert_eq!(test_result.ranks, vec![7]);
+}
+
+#[test]
+fn test_wheel_is_five_high() {
+    let mut wheel = vec![
+        Card::new(Suit::Hearts, Value::Ace),
+        Card::new(Suit::Diamonds, Value::Two),
+        Card::new(Suit::Spades, Value::Two),
+        Card::new(Suit::Clubs, Value::Three),
+        Card::new(Suit::Hearts, Value::Four),
+        Card::new(Suit::Diamonds, Value::Five),
+        Card::new(Suit::Spades, Value::King),
+    ];
+    let mut six_high = vec![
+        Card::new(Suit::Hearts, Value::Six),
+        Card::new(Suit::Diamonds, Value::Two),
+        Card::new(Suit::Spades, Value::Jack),
+        Card::new(Suit::Clubs, Value::Three),
+        Card::new(Suit::Hearts, Value::Four),
+        Card::new(Suit::Diamonds, Value::Five),
+        Card::new(Suit::Spades, Value::King),
+    ];
+
+    let wheel_result = HandResult::check_hand(&mut wheel);
+    let six_high_result = HandResult::check_hand(&mut six_high);
+
+    assert_eq!(wheel_result.result, Straight);
+    assert_eq!(wheel_result.ranks, vec![5]);
+    assert!(six_high_result > wheel_result);
+}
+
+#[cfg(test)]
+fn short_deck(text: &str, trips_beat_straight: bool) -> HandResult {
+    HandResult::check_hand_with(
+        &mut parse_hand(text).unwrap(),
+        Ranking::ShortDeck {
+            trips_beat_straight,
+        },
+    )
+}
+
+#[test]
+fn test_short_deck_wheel_is_nine_high() {
+    let wheel = short_deck("Ah6d7c8s9hKdKs", false);
+    let ten_high = short_deck("Td6d7c8s9hKdKs", false);
+
+    assert_eq!(wheel.result, Straight);
+    assert_eq!(wheel.ranks, vec![9]);
+    assert_eq!(
+        wheel
+            .cards()
+            .iter()
+            .map(|c| c.value)
+            .collect::<Vec<Value>>(),
+        vec![
+            Value::Nine,
+            Value::Eight,
+            Value::Seven,
+            Value::Six,
+            Value::Ace
+        ]
+    );
+    assert!(ten_high > wheel);
+
+    // A-6-7-8-9 isn't a straight in a full deck.
+    assert_eq!(
+        HandResult::check_hand(&mut parse_hand("Ah6d7c8s9h").unwrap()).result,
+        HighCard
+    );
+}
+
+#[test]
+fn test_short_deck_flush_beats_full_house() {
+    let flush = short_deck("6h8hTh9hKh", false);
+    let full_house = short_deck("AhAdAsKcKd", false);
+    let straight_flush = short_deck("Ah6h7h8h9h", false);
+
+    assert!(flush > full_house);
+    assert!(straight_flush > flush);
+    // Equality follows the ordering.
+    assert_eq!(flush, short_deck("6s8sTs9sKs", false));
+    assert_ne!(flush, full_house);
+    assert_eq!(flush.strength(), 0);
+    assert!(
+        HandResult::check_hand(&mut parse_hand("AhAdAsKcKd").unwrap())
+            > HandResult::check_hand(&mut parse_hand("6h8hTh9hKh").unwrap())
+    );
+}
+
+#[test]
+fn test_short_deck_trips_against_straight() {
+    let trips = "7h7d7s9cKd";
+    let straight = "6h7d8s9cTd";
+
+    assert!(short_deck(straight, false) > short_deck(trips, false));
+    assert!(short_deck(trips, true) > short_deck(straight, true));
+
+    // Holding both, the hand plays as whichever the rule ranks higher.
+    let both = "6h7d8s9cTdTsTh";
+    assert_eq!(short_deck(both, false).result, Straight);
+    assert_eq!(short_deck(both, true).result, ThreeOfAKind);
+}
+
+#[test]
+fn test_straight_detection_over_all_rank_patterns() {
+    use strum::IntoEnumIterator;
+
+    // Every way of holding 5 to 7 cards, described by how many of each value
+    // (Two through Ace) are held, with at most four of any value.
+    fn patterns(
+        value: usize,
+        remaining: usize,
+        counts: &mut Vec<usize>,
+        out: &mut Vec<Vec<usize>>,
+    ) {
+        if value == 13 {
+            if remaining == 0 {
+                out.push(counts.clone());
+            }
+            return;
+        }
+
+        for count in 0..=remaining.min(4) {
+            counts.push(count);
+            patterns(value + 1, remaining - count, counts, out);
+            counts.pop();
+        }
+    }
+
+    let suits = [Suit::Clubs, Suit::Diamonds, Suit::Hearts, Suit::Spades];
+    let values = Value::iter().collect::<Vec<Value>>();
+    let mut checked = 0;
+
+    for size in 5..=7 {
+        let mut all = vec![];
+        patterns(0, size, &mut vec![], &mut all);
+
+        for counts in all {
+            let mut cards = vec![];
+            // Bit n set means a card of value n is held, with the Ace also at bit 1.
+            let mut bits = 0u32;
+
+            for (i, &count) in counts.iter().enumerate() {
+                let value = values.iter().find(|v| v.value() == i as u32 + 2).unwrap();
+                for suit in suits.iter().take(count) {
+                    cards.push(Card::new(*suit, *value));
+                }
+                if count > 0 {
+                    bits |= 1 << (i + 2);
+                }
+            }
+            if bits & (1 << 14) != 0 {
+                bits |= 1 << 1;
+            }
+
+            let expected = (5..=14)
+                .rev()
+                .find(|high| (bits >> (high - 4)) & 0b11111 == 0b11111);
+
+            let test_result = HandResult::check_straight(&mut cards, Ranking::Standard);
+
+            match expected {
+                Some(high) => {
+                    assert_eq!(test_result.result, Straight, "{:?}", counts);
+                    assert_eq!(test_result.ranks, vec![high], "{:?}", counts);
+                }
+                None => assert_eq!(test_result.result, HighCard, "{:?}", counts),
+            }
+
+            checked += 1;
+        }
+    }
+
+    assert_eq!(checked, 6175 + 18395 + 49205);
+}
+
+/// Calls `f` with the result of every `size` card hand that can be dealt from
+/// `deck`, skipping the cards before `start`.
+#[cfg(test)]
+fn for_each_hand(
+    deck: &[Card],
+    hand: &mut Vec<Card>,
+    start: usize,
+    size: usize,
+    f: &mut impl FnMut(&HandResult),
+) {
+    if hand.len() == size {
+        f(&HandResult::check_hand(&mut hand.clone()));
+        return;
+    }
+
+    for i in start..deck.len() {
+        hand.push(deck[i]);
+        for_each_hand(deck, hand, i + 1, size, f);
+        hand.pop();
+    }
+}
+
+#[cfg(test)]
+fn full_deck() -> Vec<Card> {
+    use strum::IntoEnumIterator;
+
+    Suit::iter()
+        .flat_map(|suit| Value::iter().map(move |value| Card::new(suit, value)))
+        .collect()
+}
+
+#[test]
+fn test_five_card_category_counts() {
+    let deck = full_deck();
+    // Indexed by `ResultName::value`, High Card up to Straight Flush.
+    let mut counts = [0u64; 9];
+    let mut royal_flushes = 0;
+
+    for_each_hand(&deck, &mut vec![], 0, 5, &mut |hand_result| {
+        counts[hand_result.result.value() as usize] += 1;
+        if hand_result.result == StraightFlush && hand_result.ranks == vec![14] {
+            royal_flushes += 1;
+        }
+    });
+
+    assert_eq!(
+        counts,
+        [1_302_540, 1_098_240, 123_552, 54_912, 10_200, 5_108, 3_744, 624, 40]
+    );
+    assert_eq!(counts.iter().sum::<u64>(), 2_598_960);
+    assert_eq!(royal_flushes, 4);
+}
+
+/// Walks all 133,784,560 seven card hands, so takes a few minutes even in
+/// release. Run with `cargo test --release -- --ignored`.
+#[test]
+#[ignore]
+fn test_seven_card_category_counts() {
+    let deck = full_deck();
+    let mut counts = [0u64; 9];
+    let mut royal_flushes = 0;
+
+    for_each_hand(&deck, &mut vec![], 0, 7, &mut |hand_result| {
+        counts[hand_result.result.value() as usize] += 1;
+        if hand_result.result == StraightFlush && hand_result.ranks == vec![14] {
+            royal_flushes += 1;
+        }
+    });
+
+    assert_eq!(
+        counts,
+        [
+            23_294_460, 58_627_800, 31_433_400, 6_461_620, 6_180_020, 4_047_644, 3_473_184,
+            224_848, 41_584
+        ]
+    );
+    assert_eq!(counts.iter().sum::<u64>(), 133_784_560);
+    assert_eq!(royal_flushes, 4_324);
 }
@@ -0,0 +1,866 @@
+//! No-Limit Hold'em hands played out street by street, with blinds, antes,
+//! betting and a showdown.
+//!
+//! A `Table` holds the players and their stacks between hands. Each hand it
+//! moves the button, takes the antes and blinds, deals, then asks whoever is
+//! to act for one of the `LegalActions` until the hand is won, either by
+//! everyone else folding or at the showdown.
+//!
+//! Bets and raises are given as the total the player's bet comes to on the
+//! street, so a raise to 300 over a bet of 100 is `Action::Raise(300)`. A
+//! raise must be by at least the big blind and at least as much as the last
+//! bet or raise on the street, unless it puts the player all in. An all-in
+//! raise for less than that doesn't give players who have already acted the
+//! chance to raise again.
+
+use crate::card::*;
+use crate::evaluator;
+use crate::ledger::Ledger;
+use crate::player::{Player, Status};
+use crate::pot::{self, OddChips};
+
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+
+const HOLE_CARDS: usize = 2;
+const FLOP: usize = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Blinds {
+    pub small: u64,
+    pub big: u64,
+    /// Taken from every player dealt in, before the blinds.
+    pub ante: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Round {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+    /// The hand is over, either shown down or won by everyone else folding.
+    Showdown,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Fold,
+    Check,
+    Call,
+    /// Bet, when nobody has yet, bringing the player's bet on the street to
+    /// this total.
+    Bet(u64),
+    /// Raise the bet on the street to this total.
+    Raise(u64),
+}
+
+/// What the player to act may do.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LegalActions {
+    pub fold: bool,
+    pub check: bool,
+    /// The chips needed to call, if there's a bet to call. A player without
+    /// enough calls all in for less.
+    pub call: Option<u64>,
+    /// The totals a bet can come to, the largest being all in.
+    pub bet: Option<RangeInclusive<u64>>,
+    /// The totals a raise can come to, the largest being all in.
+    pub raise: Option<RangeInclusive<u64>>,
+}
+
+impl LegalActions {
+    pub fn allows(&self, action: Action) -> bool {
+        match action {
+            Action::Fold => self.fold,
+            Action::Check => self.check,
+            Action::Call => self.call.is_some(),
+            Action::Bet(to) => self.bet.as_ref().is_some_and(|bet| bet.contains(&to)),
+            Action::Raise(to) => self.raise.as_ref().is_some_and(|raise| raise.contains(&to)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableError {
+    /// Fewer than two players have chips to play with.
+    NotEnoughPlayers,
+    HandInProgress,
+    /// There's no hand being played, or it's over.
+    NoActionDue,
+    IllegalAction(Action),
+}
+
+impl fmt::Display for TableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TableError::NotEnoughPlayers => {
+                write!(f, "A hand needs at least two players with chips.")
+            }
+            TableError::HandInProgress => write!(f, "The last hand isn't over yet."),
+            TableError::NoActionDue => write!(f, "Nobody is due to act."),
+            TableError::IllegalAction(action) => {
+                write!(f, "{:?} isn't allowed for the player to act.", action)
+            }
+        }
+    }
+}
+
+impl Error for TableError {}
+
+/// Where each player stands in the betting on the current street. Chips and
+/// whether they've folded or are all in are kept on the `Player`.
+#[derive(Debug, Clone, Default)]
+struct Seat {
+    // Whether the player has acted since the last bet or raise.
+    acted: bool,
+    // Whether the player may still raise, which an all-in raise for less than a
+    // full raise doesn't give back to players who have already acted.
+    can_raise: bool,
+}
+
+pub struct Table {
+    players: Vec<Player>,
+    blinds: Blinds,
+    button: usize,
+    hands_played: usize,
+    deck: Deck,
+    board: Vec<Card>,
+    seats: Vec<Seat>,
+    round: Round,
+    to_act: Option<usize>,
+    // The bet to match on this street, and the least a raise must add to it.
+    current_bet: u64,
+    min_raise: u64,
+    winnings: Vec<u64>,
+    ledger: Ledger,
+}
+
+impl Table {
+    /// Seats `players` in order with the button on the first of them.
+    pub fn new(mut players: Vec<Player>, blinds: Blinds) -> Table {
+        let num_players = players.len();
+        for (seat, player) in players.iter_mut().enumerate() {
+            player.seat = seat;
+        }
+
+        Table {
+            players,
+            blinds,
+            button: 0,
+            hands_played: 0,
+            deck: Deck::new(),
+            board: vec![],
+            seats: vec![Seat::default(); num_players],
+            round: Round::Showdown,
+            to_act: None,
+            current_bet: 0,
+            min_raise: 0,
+            winnings: vec![0; num_players],
+            ledger: Ledger::new(),
+        }
+    }
+
+    pub fn players(&self) -> &[Player] {
+        &self.players
+    }
+
+    /// The players, to top up stacks or move them round the table between
+    /// hands. Their chips and status mid-hand belong to the hand being
+    /// played, so this fails until it's over.
+    pub fn players_mut(&mut self) -> Result<&mut [Player], TableError> {
+        if !self.is_over() {
+            return Err(TableError::HandInProgress);
+        }
+        Ok(&mut self.players)
+    }
+
+    pub fn into_players(self) -> Vec<Player> {
+        self.players
+    }
+
+    pub fn blinds(&self) -> Blinds {
+        self.blinds
+    }
+
+    pub fn button(&self) -> usize {
+        self.button
+    }
+
+    pub fn board(&self) -> &[Card] {
+        &self.board
+    }
+
+    pub fn round(&self) -> Round {
+        self.round
+    }
+
+    /// The seat of the player due to act, if the hand is still being bet.
+    pub fn to_act(&self) -> Option<usize> {
+        self.to_act
+    }
+
+    pub fn is_over(&self) -> bool {
+        self.round == Round::Showdown
+    }
+
+    /// The bet to match on the current street.
+    pub fn current_bet(&self) -> u64 {
+        self.current_bet
+    }
+
+    /// The chips a player has put in on the current street.
+    pub fn bet(&self, seat: usize) -> u64 {
+        self.players[seat].bet
+    }
+
+    /// The chips a player has put in over the hand so far, antes included.
+    pub fn committed(&self, seat: usize) -> u64 {
+        self.players[seat].committed
+    }
+
+    pub fn is_folded(&self, seat: usize) -> bool {
+        self.players[seat].is_folded()
+    }
+
+    pub fn is_all_in(&self, seat: usize) -> bool {
+        self.players[seat].is_all_in()
+    }
+
+    /// Whether the player was dealt in and hasn't folded.
+    pub fn is_in_hand(&self, seat: usize) -> bool {
+        self.players[seat].in_hand()
+    }
+
+    /// All the chips put in so far this hand.
+    pub fn pot(&self) -> u64 {
+        self.players.iter().map(|player| player.committed).sum()
+    }
+
+    /// The chips each player took from the pot in the last hand, once it's over.
+    pub fn winnings(&self) -> &[u64] {
+        &self.winnings
+    }
+
+    /// What each player has won or lost on every hand played at the table.
+    pub fn ledger(&self) -> &Ledger {
+        &self.ledger
+    }
+
+    /// Starts a new hand dealt from `deck`, which should already be shuffled.
+    /// Players without chips sit it out. The button moves on to the next
+    /// player with chips, except on the first hand.
+    pub fn start_hand(&mut self, deck: Deck) -> Result<(), TableError> {
+        if !self.is_over() {
+            return Err(TableError::HandInProgress);
+        }
+        if self
+            .players
+            .iter()
+            .filter(|player| player.stack > 0)
+            .count()
+            < 2
+        {
+            return Err(TableError::NotEnoughPlayers);
+        }
+
+        for (i, (seat, player)) in self
+            .seats
+            .iter_mut()
+            .zip(self.players.iter_mut())
+            .enumerate()
+        {
+            *seat = Seat {
+                can_raise: true,
+                ..Seat::default()
+            };
+            // Players may have been moved round the table since the last hand.
+            player.seat = i;
+            player.new_hand();
+        }
+
+        let has_chips = |player: &Player| player.status != Status::SittingOut;
+        let dealt_in = |_, player: &Player| has_chips(player);
+        if self.hands_played > 0 || !has_chips(&self.players[self.button]) {
+            self.button = self.next_seat(self.button, dealt_in).unwrap();
+        }
+        self.hands_played += 1;
+
+        self.deck = deck;
+        self.board.clear();
+        self.winnings = vec![0; self.players.len()];
+        self.round = Round::Preflop;
+
+        for player in self.players.iter_mut().filter(|player| has_chips(player)) {
+            player.put_in(self.blinds.ante);
+            // Antes are dead money, not part of the bet to call.
+            player.bet = 0;
+        }
+
+        // Heads up the button posts the small blind and acts first before the
+        // flop.
+        let num_dealt_in = self
+            .players
+            .iter()
+            .filter(|player| has_chips(player))
+            .count();
+        let small_blind = if num_dealt_in == 2 {
+            self.button
+        } else {
+            self.next_seat(self.button, dealt_in).unwrap()
+        };
+        let big_blind = self.next_seat(small_blind, dealt_in).unwrap();
+        self.players[small_blind].put_in(self.blinds.small);
+        self.players[big_blind].put_in(self.blinds.big);
+
+        // A big blind all in for less still has to be called in full.
+        self.current_bet = self.blinds.big;
+        self.min_raise = self.blinds.big;
+
+        let first = self.next_seat(self.button, dealt_in).unwrap();
+        for _ in 0..HOLE_CARDS {
+            let mut seat = first;
+            loop {
+                let card = self.deck.deal_card().expect("The deck ran out of cards.");
+                self.players[seat].add_card(card);
+                seat = self.next_seat(seat, dealt_in).unwrap();
+                if seat == first {
+                    break;
+                }
+            }
+        }
+
+        self.to_act = self.next_to_act(big_blind);
+        if self.to_act.is_none() {
+            self.end_round();
+        }
+
+        Ok(())
+    }
+
+    /// What the player due to act may do, if anyone is.
+    pub fn legal_actions(&self) -> Option<LegalActions> {
+        let i = self.to_act?;
+        let seat = &self.seats[i];
+        let player = &self.players[i];
+        let stack = player.stack;
+        let to_call = self.current_bet.saturating_sub(player.bet);
+        let all_in = player.bet + stack;
+        // Betting into players who are all in or folded, nobody could call.
+        let contested = self
+            .players
+            .iter()
+            .enumerate()
+            .any(|(j, other)| j != i && other.can_act());
+
+        let bet = if self.current_bet == 0 && stack > 0 && contested {
+            Some(self.blinds.big.min(all_in)..=all_in)
+        } else {
+            None
+        };
+
+        let raise = if self.current_bet > 0 && stack > to_call && seat.can_raise && contested {
+            let min_to = (self.current_bet + self.min_raise).min(all_in);
+            Some(min_to..=all_in)
+        } else {
+            None
+        };
+
+        Some(LegalActions {
+            fold: true,
+            check: to_call == 0,
+            call: if to_call > 0 {
+                Some(to_call.min(stack))
+            } else {
+                None
+            },
+            bet,
+            raise,
+        })
+    }
+
+    /// Takes `action` for the player due to act, then moves the hand on.
+    pub fn act(&mut self, action: Action) -> Result<(), TableError> {
+        let legal = self.legal_actions().ok_or(TableError::NoActionDue)?;
+        if !legal.allows(action) {
+            return Err(TableError::IllegalAction(action));
+        }
+
+        let i = self.to_act.unwrap();
+        match action {
+            Action::Fold => self.players[i].fold(),
+            Action::Check => {}
+            Action::Call => {
+                self.players[i].put_in(legal.call.unwrap());
+            }
+            Action::Bet(to) | Action::Raise(to) => {
+                let bet = self.players[i].bet;
+                self.players[i].put_in(to - bet);
+
+                let increase = to - self.current_bet;
+                let full = increase >= self.min_raise;
+                if full {
+                    self.min_raise = increase;
+                }
+                self.current_bet = to;
+
+                for (j, seat) in self.seats.iter_mut().enumerate() {
+                    if j != i && self.players[j].can_act() {
+                        seat.acted = false;
+                        seat.can_raise |= full;
+                    }
+                }
+            }
+        }
+
+        self.seats[i].acted = true;
+        self.seats[i].can_raise = false;
+
+        if self
+            .players
+            .iter()
+            .filter(|player| player.in_hand())
+            .count()
+            == 1
+        {
+            self.to_act = None;
+            self.settle();
+            return Ok(());
+        }
+
+        self.to_act = self.next_to_act(i);
+        if self.to_act.is_none() {
+            self.end_round();
+        }
+
+        Ok(())
+    }
+
+    /// The next seat after `from` whose player matches `filter`, going round
+    /// the table.
+    fn next_seat(&self, from: usize, filter: impl Fn(usize, &Player) -> bool) -> Option<usize> {
+        let n = self.players.len();
+        (1..=n)
+            .map(|step| (from + step) % n)
+            .find(|&i| filter(i, &self.players[i]))
+    }
+
+    /// The next player after `from` still to act on this street.
+    fn next_to_act(&self, from: usize) -> Option<usize> {
+        let current_bet = self.current_bet;
+        self.next_seat(from, |i, player| {
+            player.can_act() && (!self.seats[i].acted || player.bet < current_bet)
+        })
+    }
+
+    /// Deals the next street once the betting on this one is done, running the
+    /// board out when at most one player can still bet.
+    fn end_round(&mut self) {
+        loop {
+            for (seat, player) in self.seats.iter_mut().zip(self.players.iter_mut()) {
+                player.bet = 0;
+                seat.acted = false;
+                seat.can_raise = true;
+            }
+            self.current_bet = 0;
+            self.min_raise = self.blinds.big;
+
+            self.round = match self.round {
+                Round::Preflop => Round::Flop,
+                Round::Flop => Round::Turn,
+                Round::Turn => Round::River,
+                Round::River | Round::Showdown => Round::Showdown,
+            };
+
+            if self.round == Round::Showdown {
+                self.settle();
+                return;
+            }
+
+            self.deck.burn();
+            let cards = if self.round == Round::Flop { FLOP } else { 1 };
+            self.board
+                .extend(self.deck.deal(cards).expect("The deck ran out of cards."));
+
+            if self
+                .players
+                .iter()
+                .filter(|player| player.can_act())
+                .count()
+                > 1
+            {
+                self.to_act = self.next_to_act(self.button);
+                return;
+            }
+        }
+    }
+
+    /// Awards the main pot and any side pots to the best hands that can win
+    /// them, and records the hand in the ledger.
+    fn settle(&mut self) {
+        self.round = Round::Showdown;
+        self.to_act = None;
+
+        let in_hand = self
+            .players
+            .iter()
+            .filter(|player| player.in_hand())
+            .count();
+        let strengths = self
+            .players
+            .iter()
+            .map(|player| {
+                if !player.in_hand() {
+                    0
+                } else if in_hand == 1 {
+                    1
+                } else {
+                    let mut cards = player.cards.clone();
+                    cards.extend_from_slice(&self.board);
+                    evaluator::strength(&cards)
+                }
+            })
+            .collect::<Vec<u16>>();
+
+        let committed = self
+            .players
+            .iter()
+            .map(|player| player.committed)
+            .collect::<Vec<u64>>();
+        let still_in = self
+            .players
+            .iter()
+            .map(|player| player.in_hand())
+            .collect::<Vec<bool>>();
+        let won = pot::settle(
+            &committed,
+            &still_in,
+            &strengths,
+            OddChips::LeftOfButton(self.button),
+        );
+
+        for (i, &won) in won.iter().enumerate() {
+            self.players[i].stack += won;
+            self.winnings[i] = won;
+        }
+
+        for player in self.players.iter_mut() {
+            player.bet = 0;
+        }
+
+        self.ledger
+            .record_hand(self.hands_played, &self.players, &self.winnings);
+    }
+}
+
+#[cfg(test)]
+fn table(stacks: &[u64], blinds: Blinds) -> Table {
+    let players = stacks
+        .iter()
+        .enumerate()
+        .map(|(i, &stack)| Player::with_stack(format!("Player {}", i + 1), stack))
+        .collect();
+    Table::new(players, blinds)
+}
+
+#[cfg(test)]
+const BLINDS: Blinds = Blinds {
+    small: 50,
+    big: 100,
+    ante: 0,
+};
+
+#[test]
+fn test_blinds_and_first_to_act() {
+    let mut table = table(&[1000, 1000, 1000, 1000], BLINDS);
+    table.start_hand(Deck::new()).unwrap();
+
+    assert_eq!(table.button(), 0);
+    assert_eq!(table.players()[3].seat, 3);
+    assert_eq!(table.bet(1), 50);
+    assert_eq!(table.bet(2), 100);
+    assert_eq!(table.to_act(), Some(3));
+    assert_eq!(table.pot(), 150);
+    assert!(table.players().iter().all(|player| player.cards.len() == 2));
+
+    let legal = table.legal_actions().unwrap();
+    assert!(!legal.check);
+    assert_eq!(legal.call, Some(100));
+    assert_eq!(legal.bet, None);
+    assert_eq!(legal.raise, Some(200..=1000));
+
+    // Everyone calls round to the big blind, who may still check or raise.
+    for _ in 0..3 {
+        table.act(Action::Call).unwrap();
+    }
+    assert_eq!(table.to_act(), Some(2));
+    let legal = table.legal_actions().unwrap();
+    assert!(legal.check);
+    assert_eq!(legal.raise, Some(200..=900 + 100));
+    table.act(Action::Check).unwrap();
+
+    // After the flop the small blind acts first, and can bet.
+    assert_eq!(table.round(), Round::Flop);
+    assert_eq!(table.board().len(), 3);
+    assert_eq!(table.to_act(), Some(1));
+    assert_eq!(table.legal_actions().unwrap().bet, Some(100..=900));
+    assert_eq!(
+        table.act(Action::Raise(200)),
+        Err(TableError::IllegalAction(Action::Raise(200)))
+    );
+}
+
+#[test]
+fn test_heads_up_button_posts_small_blind() {
+    let mut table = table(&[1000, 1000], BLINDS);
+    table.start_hand(Deck::new()).unwrap();
+
+    assert_eq!(table.bet(0), 50);
+    assert_eq!(table.bet(1), 100);
+    assert_eq!(table.to_act(), Some(0));
+
+    table.act(Action::Call).unwrap();
+    table.act(Action::Check).unwrap();
+    // After the flop the big blind acts first.
+    assert_eq!(table.to_act(), Some(1));
+}
+
+#[test]
+fn test_no_raise_when_nobody_can_call() {
+    let mut table = table(&[30, 1000], BLINDS);
+    table.start_hand(Deck::new()).unwrap();
+
+    // The small blind is all in for 30, so the big blind can only check.
+    assert!(table.is_all_in(0));
+    assert_eq!(table.to_act(), Some(1));
+    let legal = table.legal_actions().unwrap();
+    assert!(legal.check);
+    assert_eq!(legal.bet, None);
+    assert_eq!(legal.raise, None);
+}
+
+#[test]
+fn test_min_raise_follows_the_last_raise() {
+    let mut table = table(&[5000, 5000, 5000], BLINDS);
+    table.start_hand(Deck::new()).unwrap();
+
+    // A raise to 350 is a raise of 250, so the next must be to at least 600.
+    table.act(Action::Raise(350)).unwrap();
+    assert_eq!(table.legal_actions().unwrap().raise, Some(600..=5000));
+    assert_eq!(
+        table.act(Action::Raise(500)),
+        Err(TableError::IllegalAction(Action::Raise(500)))
+    );
+    table.act(Action::Raise(600)).unwrap();
+    table.act(Action::Fold).unwrap();
+    table.act(Action::Call).unwrap();
+
+    // Each street starts again with a minimum bet of the big blind.
+    assert_eq!(table.round(), Round::Flop);
+    assert_eq!(table.pot(), 100 + 600 + 600);
+    assert_eq!(table.legal_actions().unwrap().bet, Some(100..=4400));
+}
+
+#[test]
+fn test_short_all_in_raise_does_not_reopen_betting() {
+    let mut table = table(&[1000, 1000, 1000, 250], BLINDS);
+    table.start_hand(Deck::new()).unwrap();
+
+    // Seat 3 calls, the button raises to 200, and the small blind folds.
+    table.act(Action::Call).unwrap();
+    table.act(Action::Raise(200)).unwrap();
+    table.act(Action::Fold).unwrap();
+    // The big blind calls, then seat 3 moves in for 250, only 50 more.
+    table.act(Action::Call).unwrap();
+    assert_eq!(table.legal_actions().unwrap().raise, Some(250..=250));
+    table.act(Action::Raise(250)).unwrap();
+    assert!(table.is_all_in(3));
+
+    // The button already acted on a full raise, so can only call or fold.
+    assert_eq!(table.to_act(), Some(0));
+    let legal = table.legal_actions().unwrap();
+    assert_eq!(legal.call, Some(50));
+    assert_eq!(legal.raise, None);
+}
+
+#[test]
+fn test_uncontested_pot() {
+    let mut table = table(&[1000, 1000, 1000], BLINDS);
+    table.start_hand(Deck::new()).unwrap();
+
+    table.act(Action::Raise(300)).unwrap();
+    table.act(Action::Fold).unwrap();
+    table.act(Action::Fold).unwrap();
+
+    assert!(table.is_over());
+    assert!(table.board().is_empty());
+    assert_eq!(table.winnings(), [450, 0, 0]);
+    let stacks = table
+        .players()
+        .iter()
+        .map(|player| player.stack)
+        .collect::<Vec<u64>>();
+    assert_eq!(stacks, [1150, 950, 900]);
+    assert_eq!(table.act(Action::Check), Err(TableError::NoActionDue));
+}
+
+#[test]
+fn test_all_in_showdown_with_side_pot() {
+    // Seat 1 is dealt Aces, seat 2 Kings and seat 0 Queens, and the board
+    // misses everyone.
+    let deck = Deck::stacked(&parse_hand("AhKhQhAsKsQs2c3d7h8c4s9dJc5s").unwrap());
+    let mut table = table(&[1000, 300, 600], BLINDS);
+    table.start_hand(deck).unwrap();
+
+    table.act(Action::Raise(1000)).unwrap();
+    table.act(Action::Call).unwrap();
+    table.act(Action::Call).unwrap();
+
+    // Nobody is left to bet, so the board runs out.
+    assert!(table.is_over());
+    assert_eq!(table.board().len(), 5);
+    // The Aces win the main pot of 900, the Kings the side pot of 600 and the
+    // Queens get back the 400 nobody could call.
+    assert_eq!(table.winnings(), [400, 900, 600]);
+    assert_eq!(table.players()[0].stack, 400);
+}
+
+#[test]
+fn test_antes_and_button_rotation() {
+    let blinds = Blinds {
+        small: 50,
+        big: 100,
+        ante: 10,
+    };
+    let mut table = table(&[1000, 0, 1000, 1000], blinds);
+    table.start_hand(Deck::new()).unwrap();
+
+    // The empty seat is skipped for the blinds and the deal.
+    assert_eq!(table.pot(), 3 * 10 + 150);
+    assert_eq!(table.bet(2), 50);
+    assert_eq!(table.bet(3), 100);
+    assert!(table.players()[1].cards.is_empty());
+    assert_eq!(
+        table.start_hand(Deck::new()),
+        Err(TableError::HandInProgress)
+    );
+
+    while !table.is_over() {
+        table.act(Action::Fold).unwrap();
+    }
+    assert_eq!(table.winnings(), [0, 0, 0, 180]);
+    // The empty seat sat the hand out, so isn't in the ledger.
+    assert_eq!(table.ledger().entries().len(), 3);
+    assert_eq!(table.ledger().net("Player 4"), 70);
+    assert_eq!(table.players()[1].status, Status::SittingOut);
+
+    table.start_hand(Deck::new()).unwrap();
+    assert_eq!(table.button(), 2);
+    assert_eq!(table.bet(3), 50);
+    assert_eq!(table.bet(0), 100);
+}
+
+#[test]
+fn test_players_moved_between_hands() {
+    let mut table = table(&[1000, 1000, 1000], BLINDS);
+    table.start_hand(Deck::new()).unwrap();
+    while !table.is_over() {
+        table.act(Action::Fold).unwrap();
+    }
+
+    table.players_mut().unwrap().swap(0, 2);
+    table.players_mut().unwrap()[1].seat = 99;
+    table.start_hand(Deck::new()).unwrap();
+    assert!(table.players_mut().is_err());
+
+    // Seats are numbered afresh for the new hand.
+    let seats = table
+        .players()
+        .iter()
+        .map(|player| (player.name(), player.seat))
+        .collect::<Vec<(&str, usize)>>();
+    assert_eq!(seats, [("Player 3", 0), ("Player 2", 1), ("Player 1", 2)]);
+
+    // The button is on seat 1, so seat 0 is in the big blind and seat 1 acts
+    // first.
+    assert_eq!(table.to_act(), Some(1));
+    table.act(Action::Call).unwrap();
+    table.act(Action::Call).unwrap();
+    assert_eq!(table.to_act(), Some(0));
+    table.act(Action::Check).unwrap();
+    assert_eq!(table.round(), Round::Flop);
+}
+
+#[test]
+fn test_random_play_conserves_chips() {
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    let mut rng = ChaCha8Rng::seed_from_u64(7);
+    let blinds = Blinds {
+        small: 5,
+        big: 10,
+        ante: 1,
+    };
+    let stacks = [200, 500, 80, 1000, 300, 40];
+    let mut table = table(&stacks, blinds);
+    let total = stacks.iter().sum::<u64>();
+    let mut hands = 0;
+
+    for _ in 0..500 {
+        let mut deck = Deck::new();
+        deck.shuffle(&mut rng);
+        if table.start_hand(deck).is_err() {
+            break;
+        }
+        hands += 1;
+
+        while let Some(legal) = table.legal_actions() {
+            let mut choices = vec![Action::Fold];
+            if legal.check {
+                choices.push(Action::Check);
+            }
+            if legal.call.is_some() {
+                choices.push(Action::Call);
+                choices.push(Action::Call);
+            }
+            if let Some(bet) = legal.bet {
+                choices.push(Action::Bet(rng.gen_range(*bet.start(), *bet.end() + 1)));
+            }
+            if let Some(raise) = legal.raise {
+                choices.push(Action::Raise(
+                    rng.gen_range(*raise.start(), *raise.end() + 1),
+                ));
+            }
+
+            let action = choices[rng.gen_range(0, choices.len())];
+            table.act(action).unwrap();
+        }
+
+        assert!(table.is_over());
+        assert_eq!(table.winnings().iter().sum::<u64>(), table.pot());
+        assert_eq!(
+            table
+                .players()
+                .iter()
+                .map(|player| player.stack)
+                .sum::<u64>(),
+            total
+        );
+    }
+
+    // Whatever a player is up or down in the ledger is what their stack moved.
+    let ledger = table.ledger();
+    assert_eq!(ledger.num_hands(), hands);
+    for (player, &stack) in table.players().iter().zip(stacks.iter()) {
+        assert_eq!(
+            ledger.net(player.name()),
+            player.stack as i64 - stack as i64
+        );
+    }
+    assert_eq!(
+        ledger.entries().iter().map(|entry| entry.net).sum::<i64>(),
+        0
+    );
+}
@@ -0,0 +1,142 @@
+//! A running record of what each player wins or loses hand by hand over a
+//! session, so a simulation of many hands can tell who is up or down and
+//! how they got there.
+//!
+//! Players are tracked by name rather than seat, so a player keeps their
+//! record if they change seats between tables.
+
+use crate::player::{Player, Status};
+
+use std::collections::{HashMap, HashSet};
+
+/// One player's result on one hand.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// The hand it was played on, counting from 1.
+    pub hand: usize,
+    pub name: String,
+    /// The chips won from the pot less the chips put in, antes included.
+    pub net: i64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Ledger {
+    entries: Vec<Entry>,
+}
+
+impl Ledger {
+    pub fn new() -> Ledger {
+        Ledger::default()
+    }
+
+    pub fn record(&mut self, hand: usize, name: &str, net: i64) {
+        self.entries.push(Entry {
+            hand,
+            name: name.to_string(),
+            net,
+        });
+    }
+
+    /// Records the result of a finished hand for every player dealt in, from
+    /// what they put in and `winnings`, the chips each seat took from the pot.
+    /// Players sitting the hand out get no entry.
+    pub fn record_hand(&mut self, hand: usize, players: &[Player], winnings: &[u64]) {
+        for (player, &won) in players.iter().zip(winnings.iter()) {
+            if player.status != Status::SittingOut {
+                self.record(hand, player.name(), won as i64 - player.committed as i64);
+            }
+        }
+    }
+
+    /// Every entry in the order recorded.
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// The number of hands recorded.
+    pub fn num_hands(&self) -> usize {
+        self.entries
+            .iter()
+            .map(|entry| entry.hand)
+            .collect::<HashSet<usize>>()
+            .len()
+    }
+
+    /// How far up or down a player is over the session.
+    pub fn net(&self, name: &str) -> i64 {
+        self.entries
+            .iter()
+            .filter(|entry| entry.name == name)
+            .map(|entry| entry.net)
+            .sum()
+    }
+
+    /// A player's running total after each hand they played, as
+    /// `(hand, total)`.
+    pub fn history(&self, name: &str) -> Vec<(usize, i64)> {
+        let mut total = 0;
+        self.entries
+            .iter()
+            .filter(|entry| entry.name == name)
+            .map(|entry| {
+                total += entry.net;
+                (entry.hand, total)
+            })
+            .collect()
+    }
+
+    /// Every player's total over the session, biggest winner first. Players
+    /// level on chips are in name order.
+    pub fn standings(&self) -> Vec<(String, i64)> {
+        let mut totals = HashMap::<&str, i64>::new();
+        for entry in self.entries.iter() {
+            *totals.entry(&entry.name).or_default() += entry.net;
+        }
+
+        let mut standings = totals
+            .into_iter()
+            .map(|(name, net)| (name.to_string(), net))
+            .collect::<Vec<(String, i64)>>();
+        standings.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        standings
+    }
+}
+
+#[test]
+fn test_record_hands() {
+    let mut players = vec![
+        Player::with_stack("Alice".to_string(), 0),
+        Player::with_stack("Bob".to_string(), 0),
+        Player::with_stack("Carol".to_string(), 0),
+    ];
+    let mut ledger = Ledger::new();
+
+    // Alice wins a pot of 300 that everyone put 100 into.
+    for player in players.iter_mut() {
+        player.committed = 100;
+    }
+    ledger.record_hand(1, &players, &[300, 0, 0]);
+
+    // Carol sits out, and Bob takes 50 from Alice.
+    players[0].committed = 50;
+    players[1].committed = 50;
+    players[2].committed = 0;
+    players[2].status = Status::SittingOut;
+    ledger.record_hand(2, &players, &[0, 100, 0]);
+
+    assert_eq!(ledger.num_hands(), 2);
+    assert_eq!(ledger.entries().len(), 5);
+    assert_eq!(ledger.net("Alice"), 150);
+    assert_eq!(ledger.net("Bob"), -50);
+    assert_eq!(ledger.net("Dave"), 0);
+    assert_eq!(ledger.history("Alice"), [(1, 200), (2, 150)]);
+    assert_eq!(ledger.history("Carol"), [(1, -100)]);
+    assert_eq!(
+        ledger.standings(),
+        [
+            ("Alice".to_string(), 150),
+            ("Bob".to_string(), -50),
+            ("Carol".to_string(), -100),
+        ]
+    );
+}
@@ -0,0 +1,16 @@
+pub mod card;
+mod combo;
+pub mod draw;
+pub mod equity;
+pub mod evaluator;
+pub mod hand_result;
+pub mod holdem;
+pub mod ledger;
+pub mod lowball;
+pub mod omaha;
+pub mod outs;
+pub mod player;
+pub mod pot;
+pub mod range;
+pub mod stud;
+pub mod wild;
@@ -0,0 +1,430 @@
+//! Low hand evaluation, and splitting the pot in high-low games.
+//!
+//! - Ace-to-five: Aces are low and straights and flushes don't count, so
+//!   5-4-3-2-A is the best hand. Hi-lo games only pay a low of eight or better.
+//! - Deuce-to-seven: Aces are high and straights and flushes count against the
+//!   hand, so 7-5-4-3-2 in at least two suits is the best hand.
+//! - Badugi: the most cards of different suits and ranks, Aces low, so a
+//!   four card A-2-3-4 in four suits is the best hand.
+//!
+//! A `LowResult` compares like a `HandResult`, with the better hand greater.
+
+use crate::card::*;
+use crate::combo::for_each_combination;
+use crate::evaluator;
+
+use std::cmp::Ordering;
+use std::fmt;
+
+const HAND_SIZE: usize = 5;
+const BADUGI_SIZE: usize = 4;
+const EIGHT: u8 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LowGame {
+    AceToFive,
+    DeuceToSeven,
+    Badugi,
+}
+
+#[derive(Debug, Clone, Eq)]
+pub struct LowResult {
+    game: LowGame,
+    // Compared in order, lower is better. For five card lows this is the high
+    // hand category that counts against the hand followed by the ranks, pairs
+    // first, and for Badugi the number of cards missing followed by the ranks.
+    key: Vec<u8>,
+    // The cards making up the hand, in the same order as the ranks in `key`.
+    cards: Vec<Card>,
+}
+
+impl LowResult {
+    pub fn game(&self) -> LowGame {
+        self.game
+    }
+
+    /// The cards making up this hand, highest first.
+    pub fn cards(&self) -> &[Card] {
+        &self.cards
+    }
+
+    /// Whether this is an ace-to-five low with no pair and nothing above an
+    /// Eight, the least a hi-lo game pays the low half of the pot for.
+    pub fn is_eight_or_better(&self) -> bool {
+        self.game == LowGame::AceToFive && self.key[0] == 0 && self.key[1] <= EIGHT
+    }
+}
+
+/// Returns the best ace-to-five low that can be made from five or more cards.
+pub fn ace_to_five(hand: &[Card]) -> LowResult {
+    best_five(hand, LowGame::AceToFive)
+}
+
+/// Returns the best ace-to-five low that can be made from five or more cards,
+/// if it's eight or better.
+pub fn eight_or_better(hand: &[Card]) -> Option<LowResult> {
+    Some(ace_to_five(hand)).filter(LowResult::is_eight_or_better)
+}
+
+/// Returns the best deuce-to-seven low that can be made from five or more
+/// cards.
+pub fn deuce_to_seven(hand: &[Card]) -> LowResult {
+    best_five(hand, LowGame::DeuceToSeven)
+}
+
+/// Returns the best Badugi that can be made from four cards.
+pub fn badugi(hand: &[Card]) -> LowResult {
+    assert_eq!(
+        hand.len(),
+        BADUGI_SIZE,
+        "Badugi hands hold 4 cards, got {}.",
+        hand.len()
+    );
+
+    let mut best: Option<LowResult> = None;
+
+    for mask in 1..1 << BADUGI_SIZE {
+        let mut cards = (0..BADUGI_SIZE)
+            .filter(|i| mask & (1 << i) != 0)
+            .map(|i| hand[i])
+            .collect::<Vec<Card>>();
+
+        let suits = cards
+            .iter()
+            .fold(0u8, |mask, card| mask | 1 << card.suit.index());
+        let ranks = cards
+            .iter()
+            .fold(0u16, |mask, card| mask | 1 << card.value.rank());
+        if suits.count_ones() as usize != cards.len() || ranks.count_ones() as usize != cards.len()
+        {
+            continue;
+        }
+
+        cards.sort_by_key(|card| std::cmp::Reverse(low_value(card)));
+        let mut key = vec![(BADUGI_SIZE - cards.len()) as u8];
+        key.extend(cards.iter().map(low_value));
+
+        let result = LowResult {
+            game: LowGame::Badugi,
+            key,
+            cards,
+        };
+        if best.as_ref().is_none_or(|best| result > *best) {
+            best = Some(result);
+        }
+    }
+
+    best.unwrap()
+}
+
+fn best_five(hand: &[Card], game: LowGame) -> LowResult {
+    assert!(
+        hand.len() >= HAND_SIZE,
+        "Low hands need at least 5 cards, got {}.",
+        hand.len()
+    );
+
+    let mut best: Option<LowResult> = None;
+    for_each_combination(hand, HAND_SIZE, |five| {
+        let result = five_card_low(five, game);
+        if best.as_ref().is_none_or(|best| result > *best) {
+            best = Some(result);
+        }
+    });
+    best.unwrap()
+}
+
+/// Scores exactly five cards as an ace-to-five or deuce-to-seven low.
+fn five_card_low(five: &[Card], game: LowGame) -> LowResult {
+    let value = |card: &Card| match game {
+        LowGame::AceToFive => low_value(card),
+        _ => card.value.value() as u8,
+    };
+
+    let mut cards = five.to_vec();
+    let count = |card: &Card| {
+        five.iter()
+            .filter(|other| other.value == card.value)
+            .count()
+    };
+    cards.sort_by_key(|card| std::cmp::Reverse((count(card), value(card))));
+
+    let groups = cards
+        .chunk_by(|a, b| a.value == b.value)
+        .map(|group| group.len())
+        .collect::<Vec<usize>>();
+
+    // The same categories, in the same order, as a high hand.
+    let mut category = match groups[..] {
+        [4, 1] => 7,
+        [3, 2] => 6,
+        [3, 1, 1] => 3,
+        [2, 2, 1] => 2,
+        [2, 1, 1, 1] => 1,
+        _ => 0,
+    };
+
+    if game == LowGame::DeuceToSeven && category == 0 {
+        let flush = cards.iter().all(|card| card.suit == cards[0].suit);
+        let straight = value(&cards[0]) - value(&cards[4]) == 4;
+        category = match (straight, flush) {
+            (true, true) => 8,
+            (false, true) => 5,
+            (true, false) => 4,
+            (false, false) => 0,
+        };
+    }
+
+    let mut key = vec![category];
+    key.extend(cards.iter().map(value));
+
+    LowResult { game, key, cards }
+}
+
+/// A card's value with Aces low, from 1 to 13.
+pub(crate) fn low_value(card: &Card) -> u8 {
+    match card.value {
+        Value::Ace => 1,
+        value => value.value() as u8,
+    }
+}
+
+/// Each player's share of the pot in a high-low split game. The best high hand
+/// takes half the pot and the best qualifying low the other half, or the high
+/// hand scoops the lot if there is no qualifying low. Ties split their half.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HiLoShares {
+    pub high: Vec<f64>,
+    pub low: Vec<f64>,
+}
+
+impl HiLoShares {
+    /// Splits the pot given each player's high hand strength and qualifying low,
+    /// if any.
+    pub fn split<H: Ord>(highs: &[H], lows: &[Option<LowResult>]) -> HiLoShares {
+        let best_low = lows.iter().flatten().max();
+        let high_half = if best_low.is_some() { 0.5 } else { 1.0 };
+
+        HiLoShares {
+            high: split_half(&highs.iter().map(Some).collect::<Vec<_>>(), high_half),
+            low: split_half(
+                &lows.iter().map(Option::as_ref).collect::<Vec<_>>(),
+                1.0 - high_half,
+            ),
+        }
+    }
+
+    /// Splits the pot between seven card stud hands, each holding seven cards.
+    pub fn stud(hands: &[Vec<Card>]) -> HiLoShares {
+        let highs = hands
+            .iter()
+            .map(|hand| evaluator::strength(hand))
+            .collect::<Vec<u16>>();
+        let lows = hands
+            .iter()
+            .map(|hand| eight_or_better(hand))
+            .collect::<Vec<Option<LowResult>>>();
+
+        HiLoShares::split(&highs, &lows)
+    }
+
+    /// Splits the pot between Razz hands, each holding seven cards. The best
+    /// ace-to-five low takes the whole pot, however high it is.
+    pub fn razz(hands: &[Vec<Card>]) -> HiLoShares {
+        let lows = hands
+            .iter()
+            .map(|hand| ace_to_five(hand))
+            .collect::<Vec<LowResult>>();
+
+        HiLoShares {
+            high: vec![0.0; hands.len()],
+            low: split_half(&lows.iter().map(Some).collect::<Vec<_>>(), 1.0),
+        }
+    }
+
+    /// The whole of a player's share of the pot.
+    pub fn total(&self, player: usize) -> f64 {
+        self.high[player] + self.low[player]
+    }
+}
+
+/// Splits `amount` evenly between the players holding the best hand, where
+/// players without a hand take nothing.
+fn split_half<H: Ord>(hands: &[Option<&H>], amount: f64) -> Vec<f64> {
+    let best = hands.iter().flatten().max().copied();
+    let num_winners = hands
+        .iter()
+        .filter(|&&hand| best.is_some() && hand == best)
+        .count();
+
+    hands
+        .iter()
+        .map(|&hand| {
+            if best.is_some() && hand == best {
+                amount / num_winners as f64
+            } else {
+                0.0
+            }
+        })
+        .collect()
+}
+
+impl Ord for LowResult {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.key.cmp(&self.key)
+    }
+}
+
+impl PartialOrd for LowResult {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for LowResult {
+    fn eq(&self, other: &Self) -> bool {
+        self.key == other.key
+    }
+}
+
+impl fmt::Display for LowResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ranks = self
+            .cards
+            .iter()
+            .map(|card| {
+                "23456789TJQKA"
+                    .chars()
+                    .nth(card.value.rank() as usize)
+                    .unwrap()
+                    .to_string()
+            })
+            .collect::<Vec<String>>()
+            .join("-");
+
+        match self.game {
+            LowGame::Badugi if self.cards.len() < BADUGI_SIZE => {
+                write!(f, "{} {} Card Badugi", ranks, self.cards.len())
+            }
+            LowGame::Badugi => write!(f, "{} Badugi", ranks),
+            _ => write!(f, "{} Low", ranks),
+        }
+    }
+}
+
+#[cfg(test)]
+fn low_name(result: &LowResult) -> String {
+    format!("{}", result)
+}
+
+#[test]
+fn test_ace_to_five_ignores_straights_and_flushes() {
+    let wheel = ace_to_five(&parse_hand("Ah2h3h4h5h").unwrap());
+    let six_low = ace_to_five(&parse_hand("Ac2d3h4s6c").unwrap());
+    let paired = ace_to_five(&parse_hand("AcAd3h4s6c").unwrap());
+
+    assert_eq!(low_name(&wheel), "5-4-3-2-A Low");
+    assert!(wheel > six_low);
+    assert!(six_low > paired);
+    assert_eq!(low_name(&paired), "A-A-6-4-3 Low");
+}
+
+#[test]
+fn test_ace_to_five_picks_the_best_five() {
+    let low = ace_to_five(&parse_hand("KcKd7h5s3c2dAh").unwrap());
+    assert_eq!(low_name(&low), "7-5-3-2-A Low");
+
+    // Fewer than five ranks means a pair has to play, the lowest pair first.
+    let low = ace_to_five(&parse_hand("2c2d3h3s4c4dKh").unwrap());
+    assert_eq!(low_name(&low), "2-2-K-4-3 Low");
+}
+
+#[test]
+fn test_eight_or_better_qualifier() {
+    assert!(eight_or_better(&parse_hand("8c7d6h5s4c").unwrap()).is_some());
+    assert!(eight_or_better(&parse_hand("9c4d3h2sAc").unwrap()).is_none());
+    assert!(eight_or_better(&parse_hand("AcAd3h4s6c").unwrap()).is_none());
+    assert!(!deuce_to_seven(&parse_hand("7c5d4h3s2c").unwrap()).is_eight_or_better());
+}
+
+#[test]
+fn test_deuce_to_seven_counts_straights_and_flushes() {
+    let seven_five = deuce_to_seven(&parse_hand("7c5d4h3s2c").unwrap());
+    let eight_low = deuce_to_seven(&parse_hand("8c5d4h3s2c").unwrap());
+    let wheel = deuce_to_seven(&parse_hand("Ac2d3h4s5c").unwrap());
+    let straight = deuce_to_seven(&parse_hand("6c5d4h3s2c").unwrap());
+    let flush = deuce_to_seven(&parse_hand("7c5c4c3c2c").unwrap());
+    let pair = deuce_to_seven(&parse_hand("2c2d4h3s5c").unwrap());
+
+    assert_eq!(low_name(&seven_five), "7-5-4-3-2 Low");
+    assert!(seven_five > eight_low);
+    // The Ace plays high, so A-5-4-3-2 is an Ace high hand rather than a straight.
+    assert_eq!(low_name(&wheel), "A-5-4-3-2 Low");
+    assert!(eight_low > wheel);
+    assert!(wheel > pair);
+    assert!(pair > straight);
+    assert!(straight > flush);
+}
+
+#[test]
+fn test_badugi() {
+    let best = badugi(&parse_hand("Ac2d3h4s").unwrap());
+    let king_high = badugi(&parse_hand("Kc2d3h4s").unwrap());
+    let three_card = badugi(&parse_hand("Ac2c3h4s").unwrap());
+    let paired = badugi(&parse_hand("Ac2d2h4s").unwrap());
+    let one_card = badugi(&parse_hand("AcKcQcJc").unwrap());
+
+    assert_eq!(low_name(&best), "4-3-2-A Badugi");
+    assert!(best > king_high);
+    // Any four card Badugi beats every three card hand.
+    assert!(king_high > three_card);
+    assert_eq!(low_name(&three_card), "4-3-A 3 Card Badugi");
+    assert_eq!(low_name(&paired), "4-2-A 3 Card Badugi");
+    assert_eq!(low_name(&one_card), "A 1 Card Badugi");
+}
+
+#[test]
+fn test_hi_lo_split() {
+    let lows = [
+        eight_or_better(&parse_hand("Ac2d3h4s6c").unwrap()),
+        eight_or_better(&parse_hand("As2c3d4h6s").unwrap()),
+        None,
+        eight_or_better(&parse_hand("8c7d6h5s4c").unwrap()),
+    ];
+
+    // Two players tie the low, while a third takes the high.
+    let shares = HiLoShares::split(&[1, 2, 9, 3], &lows);
+    assert_eq!(shares.high, [0.0, 0.0, 0.5, 0.0]);
+    assert_eq!(shares.low, [0.25, 0.25, 0.0, 0.0]);
+
+    // No qualifying low, so the high hands split the whole pot.
+    let shares = HiLoShares::split(&[9, 2, 9], &[None, None, None]);
+    assert_eq!(shares.high, [0.5, 0.0, 0.5]);
+    assert_eq!(shares.low, [0.0, 0.0, 0.0]);
+}
+
+#[test]
+fn test_razz() {
+    let hands = [
+        // A pair of Kings, but 9-7-6-3-2 is the low.
+        parse_hand("KcKd9h7s6c3d2h").unwrap(),
+        parse_hand("AcAd2s3h4dQcKh").unwrap(),
+        parse_hand("Ah2c3s4h9dQsKs").unwrap(),
+    ];
+
+    let shares = HiLoShares::razz(&hands);
+    assert_eq!(shares.high, [0.0, 0.0, 0.0]);
+    assert_eq!(shares.low, [0.0, 0.0, 1.0]);
+}
+
+#[test]
+fn test_stud_hi_lo() {
+    let hands = [
+        parse_hand("KcKdKhKs2c3d9h").unwrap(),
+        parse_hand("Ac2d3h4s6c9cTd").unwrap(),
+    ];
+
+    let shares = HiLoShares::stud(&hands);
+    assert_eq!(shares.total(0), 0.5);
+    assert_eq!(shares.total(1), 0.5);
+}
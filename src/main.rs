@@ -2,135 +2,994 @@ extern crate rand;
 extern crate strum;
 extern crate strum_macros;
 
-mod card;
-mod hand_result;
-mod player;
-
-use card::*;
-use player::*;
+use rusty_poker2::card::*;
+use rusty_poker2::draw::{self, Strategy};
+use rusty_poker2::equity::{self, EquityOptions};
+use rusty_poker2::evaluator;
+use rusty_poker2::hand_result::{HandResult, Ranking};
+use rusty_poker2::lowball::LowResult;
+use rusty_poker2::omaha;
+use rusty_poker2::outs;
+use rusty_poker2::player::*;
+use rusty_poker2::range::Range;
+use rusty_poker2::stud::{self, Street, StudGame};
 
 use std::error::Error;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
 use std::process;
+use std::str::FromStr;
+use std::sync::mpsc;
+use std::thread;
 use std::time::Instant;
 
-use rand::seq::SliceRandom;
-use rand::thread_rng;
-use serde::Serialize;
-use strum::IntoEnumIterator;
+use clap::{App, Arg, ArgMatches, SubCommand};
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 
-const NUM_PLAYERS: usize = 8;
+const MIN_PLAYERS: usize = 2;
+const MAX_PLAYERS: usize = 10;
+const DEFAULT_PLAYERS: usize = 8;
+const GAMES: [&str; 10] = [
+    "holdem",
+    "plo4",
+    "plo5",
+    "plo6",
+    "plo8",
+    "shortdeck",
+    "stud",
+    "stud8",
+    "razz",
+    "draw",
+];
+const BOARD_SIZE: usize = 5;
+// Trials are handed to the worker threads in chunks of this many.
+const CHUNK_SIZE: usize = 1000;
+// How many finished chunks each worker may queue up for the writer.
+const CHUNKS_IN_FLIGHT: usize = 4;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "PascalCase")]
 struct Record {
     player_number: usize,
     trial_number: usize,
+    // The seed the trial was shuffled with, to replay it with `--replay`. It's
+    // a column rather than a header line so CSV and JSON line readers need no
+    // special handling.
+    seed: u64,
     card_1: Card,
     card_2: Card,
     card_3: Card,
     card_4: Card,
     card_5: Card,
-    card_6: Card,
-    card_7: Card,
+    // Draw hands only hold five cards.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    card_6: Option<Card>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    card_7: Option<Card>,
+    // Omaha hands hold more cards, these columns are left out for Hold'em.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    card_8: Option<Card>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    card_9: Option<Card>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    card_10: Option<Card>,
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    card_11: Option<Card>,
     winner: bool,
+    share: f64,
     result_name: Option<String>,
+    // How many cards the player drew, in draw games.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    drawn: Option<usize>,
 }
 
-fn run() -> Result<(), Box<dyn Error>> {
-    let now = Instant::now();
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Game {
+    Holdem,
+    // Pot Limit Omaha, with the number of hole cards dealt.
+    Omaha(usize),
+    // Omaha with the pot split between the best high and eight or better low.
+    OmahaHiLo(usize),
+    // Hold'em with a 36 card deck.
+    ShortDeck(Ranking),
+    // Seven cards each and no board.
+    Stud(StudGame),
+    // Five card draw.
+    Draw,
+}
+
+impl Game {
+    fn hole_cards(&self) -> usize {
+        match self {
+            Game::Holdem | Game::ShortDeck(_) => 2,
+            Game::Omaha(n) | Game::OmahaHiLo(n) => *n,
+            Game::Stud(_) => stud::HAND_SIZE,
+            Game::Draw => draw::HAND_SIZE,
+        }
+    }
+
+    /// The most cards each player can take from the deck, counting the draw.
+    fn cards_per_player(&self) -> usize {
+        match self {
+            Game::Draw => 2 * draw::HAND_SIZE,
+            _ => self.hole_cards(),
+        }
+    }
+
+    fn board_cards(&self) -> usize {
+        match self {
+            Game::Stud(_) | Game::Draw => 0,
+            _ => BOARD_SIZE,
+        }
+    }
+
+    /// The players dealt when `--players` isn't given: as many as the game
+    /// can deal, up to `DEFAULT_PLAYERS`.
+    fn default_players(&self) -> usize {
+        match self {
+            Game::Stud(_) => stud::MAX_PLAYERS,
+            Game::Draw => draw::MAX_PLAYERS,
+            _ => DEFAULT_PLAYERS
+                .min((self.deck().len() - self.board_cards()) / self.cards_per_player()),
+        }
+    }
+
+    fn deck(&self) -> Deck {
+        match self {
+            Game::ShortDeck(_) => Deck::short(),
+            _ => Deck::new(),
+        }
+    }
+
+    /// Fills `strengths` with the strength of each player's hand, the hole cards
+    /// followed by the board. Only the order of the strengths means anything.
+    fn strengths(&self, players: &[Player], strengths: &mut Vec<u16>) {
+        strengths.clear();
+
+        match self {
+            Game::Holdem | Game::Stud(_) | Game::Draw => {
+                strengths.extend(
+                    players
+                        .iter()
+                        .map(|player| evaluator::strength(&player.cards)),
+                );
+            }
+            Game::Omaha(n) | Game::OmahaHiLo(n) => {
+                strengths.extend(
+                    players
+                        .iter()
+                        .map(|player| omaha::strength(&player.cards[..*n], &player.cards[*n..])),
+                );
+            }
+            Game::ShortDeck(_) => {
+                // There are no strength tables for a short deck, so the hands are
+                // only ranked against each other.
+                let results = players
+                    .iter()
+                    .map(|player| self.evaluate(&player.cards))
+                    .collect::<Vec<HandResult>>();
+                strengths.extend(
+                    results.iter().map(|result| {
+                        results.iter().filter(|other| *other < result).count() as u16
+                    }),
+                );
+            }
+        }
+    }
+
+    fn evaluate(&self, cards: &[Card]) -> HandResult {
+        match self {
+            Game::Holdem | Game::Stud(_) | Game::Draw => HandResult::evaluate(cards),
+            Game::Omaha(n) | Game::OmahaHiLo(n) => omaha::evaluate(&cards[..*n], &cards[*n..]),
+            Game::ShortDeck(ranking) => HandResult::check_hand_with(&mut cards.to_vec(), *ranking),
+        }
+    }
+
+    /// The qualifying low made by a hand, in games that split the pot.
+    fn evaluate_low(&self, cards: &[Card]) -> Option<LowResult> {
+        match self {
+            Game::OmahaHiLo(n) => omaha::eight_or_better(&cards[..*n], &cards[*n..]),
+            Game::Stud(stud_game) => stud::evaluate_low(*stud_game, cards),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Game {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Game, String> {
+        match text {
+            "holdem" => Ok(Game::Holdem),
+            "plo4" => Ok(Game::Omaha(4)),
+            "plo5" => Ok(Game::Omaha(5)),
+            "plo6" => Ok(Game::Omaha(6)),
+            "plo8" => Ok(Game::OmahaHiLo(4)),
+            "shortdeck" => Ok(Game::ShortDeck(Ranking::ShortDeck {
+                trips_beat_straight: false,
+            })),
+            "stud" => Ok(Game::Stud(StudGame::Stud)),
+            "stud8" => Ok(Game::Stud(StudGame::StudHiLo)),
+            "razz" => Ok(Game::Stud(StudGame::Razz)),
+            "draw" => Ok(Game::Draw),
+            _ => Err(format!("'{}' is not a game.", text)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Format {
+    Csv,
+    Tsv,
+    // One JSON object per line.
+    Json,
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(text: &str) -> Result<Format, String> {
+        match text {
+            "csv" => Ok(Format::Csv),
+            "tsv" => Ok(Format::Tsv),
+            "json" => Ok(Format::Json),
+            _ => Err(format!("'{}' is not an output format.", text)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+#[derive(Debug)]
+struct Config {
+    game: Game,
+    // What each seat throws away in draw games, taken in turn.
+    strategies: Vec<Strategy>,
+    num_players: usize,
+    num_trials: usize,
+    output: String,
+    format: Format,
+    seed: u64,
+    num_threads: usize,
+    // Deal only this trial, to reproduce it from an earlier run with the same seed.
+    replay: Option<usize>,
+    verbosity: Verbosity,
+}
+
+#[derive(Debug)]
+struct EquityConfig {
+    ranges: Vec<Range>,
+    board: Vec<Card>,
+    dead: Vec<Card>,
+    options: EquityOptions,
+}
+
+#[derive(Debug)]
+struct OutsConfig {
+    hand: [Card; 2],
+    board: Vec<Card>,
+    opponent: Option<[Card; 2]>,
+}
+
+#[derive(Debug)]
+enum Command {
+    Simulate(Config),
+    Equity(EquityConfig),
+    Outs(OutsConfig),
+}
+
+impl Command {
+    /// Reads the command from the command line, printing help or usage errors
+    /// and exiting if needed.
+    fn from_args() -> Command {
+        // Games that can't deal `DEFAULT_PLAYERS` default to fewer.
+        let fewer_players = GAMES
+            .iter()
+            .map(|name| (name, name.parse::<Game>().unwrap().default_players()))
+            .filter(|&(_, players)| players < DEFAULT_PLAYERS)
+            .map(|(name, players)| format!("{} in {}", players, name))
+            .collect::<Vec<String>>();
+        let players_help = format!(
+            "Number of players at the table, from {} to {}. Defaults to {}, or as many as the \
+             game can deal: {}",
+            MIN_PLAYERS,
+            MAX_PLAYERS,
+            DEFAULT_PLAYERS,
+            fewer_players.join(", ")
+        );
+
+        let matches = App::new("rusty_poker2")
+            .about("Simulates Texas Hold'em, Omaha, stud or draw showdowns and records every player's hand.")
+            .subcommand(
+                SubCommand::with_name("equity")
+                    .about("Works out the equity of hands or ranges, e.g. equity AhKh \"QQ+, AKs\" -b 7c8h2d")
+                    .arg(
+                        Arg::with_name("ranges")
+                            .value_name("RANGE")
+                            .help("Hole cards or a range such as \"QQ+, AKs, A5s-A2s\" for each player")
+                            .required(true)
+                            .min_values(2)
+                            .validator(|v| v.parse::<Range>().map(|_| ()).map_err(|e| e.to_string())),
+                    )
+                    .arg(
+                        Arg::with_name("board")
+                            .short("b")
+                            .long("board")
+                            .value_name("CARDS")
+                            .help("Cards already on the board")
+                            .validator(|v| parse_cards(&v).map(|_| ())),
+                    )
+                    .arg(
+                        Arg::with_name("dead")
+                            .short("d")
+                            .long("dead")
+                            .value_name("CARDS")
+                            .help("Cards known to be out of the deck")
+                            .validator(|v| parse_cards(&v).map(|_| ())),
+                    )
+                    .arg(
+                        Arg::with_name("samples")
+                            .short("n")
+                            .long("samples")
+                            .value_name("N")
+                            .help("Boards to sample when there are too many to deal them all out")
+                            .validator(|v| parse_number::<u64>(&v).map(|_| ())),
+                    )
+                    .arg(
+                        Arg::with_name("seed")
+                            .short("s")
+                            .long("seed")
+                            .value_name("SEED")
+                            .help("Seed for sampling, random if not given")
+                            .validator(|v| parse_number::<u64>(&v).map(|_| ())),
+                    ),
+            )
+            .subcommand(
+                SubCommand::with_name("outs")
+                    .about("Lists the cards that would help a hand, e.g. outs 6h7h -b 8h9h2c -a AsAd")
+                    .arg(
+                        Arg::with_name("hand")
+                            .value_name("HAND")
+                            .help("The two hole cards to find outs for")
+                            .required(true)
+                            .validator(|v| parse_hole_cards(&v).map(|_| ())),
+                    )
+                    .arg(
+                        Arg::with_name("board")
+                            .short("b")
+                            .long("board")
+                            .value_name("CARDS")
+                            .help("The flop or turn")
+                            .required(true)
+                            .validator(|v| parse_cards(&v).map(|_| ())),
+                    )
+                    .arg(
+                        Arg::with_name("against")
+                            .short("a")
+                            .long("against")
+                            .value_name("HAND")
+                            .help("An opponent's hole cards, to find the cards that take the lead")
+                            .validator(|v| parse_hole_cards(&v).map(|_| ())),
+                    ),
+            )
+            .arg(
+                Arg::with_name("game")
+                    .short("g")
+                    .long("game")
+                    .value_name("GAME")
+                    .help("Game to deal, Hold'em, 4, 5 or 6 card Pot Limit Omaha, Omaha Hi-Lo, short deck Hold'em, seven card stud, stud hi-lo, Razz or five card draw")
+                    .possible_values(&GAMES)
+                    .default_value("holdem"),
+            )
+            .arg(
+                Arg::with_name("strategy")
+                    .long("strategy")
+                    .value_name("STRATEGY")
+                    .help("In draw, what each seat throws away, taken in turn from a comma separated list")
+                    .possible_values(&["keep-made", "stand-pat"])
+                    .use_delimiter(true)
+                    .multiple(true),
+            )
+            .arg(
+                Arg::with_name("trips-beat-straight")
+                    .long("trips-beat-straight")
+                    .help("In short deck, rank Three Of A Kind above a Straight"),
+            )
+            .arg(
+                Arg::with_name("players")
+                    .short("p")
+                    .long("players")
+                    .value_name("N")
+                    .help(&players_help)
+                    .validator(|v| {
+                        let n = parse_number::<usize>(&v)?;
+                        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&n) {
+                            return Err(format!(
+                                "must be between {} and {}",
+                                MIN_PLAYERS, MAX_PLAYERS
+                            ));
+                        }
+                        Ok(())
+                    }),
+            )
+            .arg(
+                Arg::with_name("trials")
+                    .short("t")
+                    .long("trials")
+                    .value_name("N")
+                    .help("Number of hands to deal")
+                    .default_value("1000")
+                    .validator(|v| parse_number::<usize>(&v).map(|_| ())),
+            )
+            .arg(
+                Arg::with_name("output")
+                    .short("o")
+                    .long("output")
+                    .value_name("PATH")
+                    .help("File to write the results to, or - for stdout")
+                    .default_value("out.csv"),
+            )
+            .arg(
+                Arg::with_name("format")
+                    .short("f")
+                    .long("format")
+                    .value_name("FORMAT")
+                    .help("Output format, json writes one object per line")
+                    .possible_values(&["csv", "tsv", "json"])
+                    .default_value("csv"),
+            )
+            .arg(
+                Arg::with_name("seed")
+                    .short("s")
+                    .long("seed")
+                    .value_name("SEED")
+                    .help(
+                        "Seed for shuffling, random if not given. Recorded in the Seed column of \
+                         every record rather than a header line, so the output stays plain CSV or \
+                         JSON lines",
+                    )
+                    .validator(|v| parse_number::<u64>(&v).map(|_| ())),
+            )
+            .arg(
+                Arg::with_name("replay")
+                    .short("r")
+                    .long("replay")
+                    .value_name("TRIAL")
+                    .help("Deal only this trial number, exactly as it was dealt with the same seed")
+                    .requires("seed")
+                    .validator(|v| {
+                        if parse_number::<usize>(&v)? == 0 {
+                            return Err("trials are numbered from 1".to_string());
+                        }
+                        Ok(())
+                    }),
+            )
+            .arg(
+                Arg::with_name("threads")
+                    .short("j")
+                    .long("threads")
+                    .value_name("N")
+                    .help("Number of worker threads, defaults to one per CPU")
+                    .validator(|v| {
+                        if parse_number::<usize>(&v)? == 0 {
+                            return Err("must be at least 1".to_string());
+                        }
+                        Ok(())
+                    }),
+            )
+            .arg(
+                Arg::with_name("quiet")
+                    .short("q")
+                    .long("quiet")
+                    .help("Print nothing but errors")
+                    .conflicts_with("verbose"),
+            )
+            .arg(
+                Arg::with_name("verbose")
+                    .short("v")
+                    .long("verbose")
+                    .help("Print every hand dealt and who won it"),
+            )
+            .get_matches();
+
+        match matches.subcommand() {
+            ("equity", Some(matches)) => Command::Equity(EquityConfig::from_matches(matches)),
+            ("outs", Some(matches)) => Command::Outs(OutsConfig::from_matches(matches)),
+            _ => Command::Simulate(Config::from_matches(&matches)),
+        }
+    }
+}
 
-    let mut rng = thread_rng();
+// Clap has validated every value by the time these run, so the parsing in
+// each `from_matches` can't fail.
 
-    let file_path = "out.csv";
-    let mut wtr = csv::Writer::from_path(file_path)?;
+impl EquityConfig {
+    fn from_matches(matches: &ArgMatches) -> EquityConfig {
+        let cards = |name| {
+            matches
+                .value_of(name)
+                .map_or_else(Vec::new, |v| parse_cards(v).unwrap())
+        };
+        let mut options = EquityOptions::default();
+        if let Some(samples) = matches.value_of("samples") {
+            options.samples = samples.parse().unwrap();
+        }
+        options.seed = matches.value_of("seed").map(|v| v.parse().unwrap());
 
-    for trial_number in 1..1000 {
-        let mut players = Vec::<Player>::new();
-        let mut cards = Vec::<Card>::new();
-        let mut table_cards = Player::new("Table".to_string());
+        EquityConfig {
+            ranges: matches
+                .values_of("ranges")
+                .unwrap()
+                .map(|v| v.parse().unwrap())
+                .collect(),
+            board: cards("board"),
+            dead: cards("dead"),
+            options,
+        }
+    }
+}
 
-        for suit in Suit::iter() {
-            for value in Value::iter() {
-                cards.push(Card::new(suit, value));
+impl OutsConfig {
+    fn from_matches(matches: &ArgMatches) -> OutsConfig {
+        OutsConfig {
+            hand: parse_hole_cards(matches.value_of("hand").unwrap()).unwrap(),
+            board: parse_cards(matches.value_of("board").unwrap()).unwrap(),
+            opponent: matches
+                .value_of("against")
+                .map(|v| parse_hole_cards(v).unwrap()),
+        }
+    }
+}
+
+impl Config {
+    fn from_matches(matches: &ArgMatches) -> Config {
+        let verbosity = if matches.is_present("quiet") {
+            Verbosity::Quiet
+        } else if matches.is_present("verbose") {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        };
+
+        let mut game: Game = matches.value_of("game").unwrap().parse().unwrap();
+
+        if matches.is_present("trips-beat-straight") {
+            match game {
+                Game::ShortDeck(_) => {
+                    game = Game::ShortDeck(Ranking::ShortDeck {
+                        trips_beat_straight: true,
+                    })
+                }
+                _ => clap::Error::with_description(
+                    "--trips-beat-straight only applies to --game shortdeck",
+                    clap::ErrorKind::ArgumentConflict,
+                )
+                .exit(),
             }
         }
+        let strategies = match matches.values_of("strategy") {
+            Some(_) if game != Game::Draw => clap::Error::with_description(
+                "--strategy only applies to --game draw",
+                clap::ErrorKind::ArgumentConflict,
+            )
+            .exit(),
+            Some(values) => values.map(|v| v.parse().unwrap()).collect(),
+            None => vec![Strategy::KeepMadeHands],
+        };
+        let num_players = matches
+            .value_of("players")
+            .map_or_else(|| game.default_players(), |v| v.parse().unwrap());
+
+        if num_players * game.cards_per_player() + game.board_cards() > game.deck().len() {
+            clap::Error::with_description(
+                &format!(
+                    "There aren't enough cards to deal {} players {} cards each",
+                    num_players,
+                    game.cards_per_player()
+                ),
+                clap::ErrorKind::ValueValidation,
+            )
+            .exit();
+        }
+
+        Config {
+            game,
+            strategies,
+            num_players,
+            num_trials: matches.value_of("trials").unwrap().parse().unwrap(),
+            output: matches.value_of("output").unwrap().to_string(),
+            format: matches.value_of("format").unwrap().parse().unwrap(),
+            seed: matches
+                .value_of("seed")
+                .map_or_else(rand::random, |v| v.parse().unwrap()),
+            num_threads: matches.value_of("threads").map_or_else(
+                || thread::available_parallelism().map_or(1, |n| n.get()),
+                |v| v.parse().unwrap(),
+            ),
+            replay: matches.value_of("replay").map(|v| v.parse().unwrap()),
+            verbosity,
+        }
+    }
+}
 
-        // println!("Shuffling!");
-        cards.shuffle(&mut rng);
+fn parse_number<T: FromStr>(text: &str) -> Result<T, String> {
+    text.parse()
+        .map_err(|_| format!("'{}' is not a valid whole number", text))
+}
+
+fn parse_cards(text: &str) -> Result<Vec<Card>, String> {
+    parse_hand(text).map_err(|err| err.to_string())
+}
+
+fn parse_hole_cards(text: &str) -> Result<[Card; 2], String> {
+    match parse_cards(text)?[..] {
+        [first, second] => Ok([first, second]),
+        _ => Err(format!("'{}' is not two hole cards", text)),
+    }
+}
+
+/// Returns the generator used to shuffle the deck for one trial. Every trial gets
+/// its own stream, so any trial can be dealt again from just the seed and its
+/// trial number.
+fn trial_rng(seed: u64, trial_number: usize) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    rng.set_stream(trial_number as u64);
+    rng
+}
+
+/// Writes records to a file or stdout in the chosen format. Delimited output
+/// starts with a header row, and JSON output has one object per line.
+enum RecordWriter {
+    Delimited(Box<csv::Writer<Box<dyn Write>>>),
+    Json(Box<dyn Write>),
+}
 
-        for i in 0..NUM_PLAYERS {
-            let player_name = format!("Player {}", i + 1);
-            players.push(Player::new(player_name));
+impl RecordWriter {
+    fn new(path: &str, format: Format) -> Result<RecordWriter, Box<dyn Error>> {
+        let out: Box<dyn Write> = if path == "-" {
+            Box::new(io::stdout())
+        } else {
+            Box::new(BufWriter::new(File::create(path)?))
+        };
+
+        let delimiter = match format {
+            Format::Csv => b',',
+            Format::Tsv => b'\t',
+            Format::Json => return Ok(RecordWriter::Json(out)),
+        };
+
+        Ok(RecordWriter::Delimited(Box::new(
+            csv::WriterBuilder::new()
+                .delimiter(delimiter)
+                .from_writer(out),
+        )))
+    }
+
+    fn write(&mut self, record: &Record) -> Result<(), Box<dyn Error>> {
+        match self {
+            RecordWriter::Delimited(wtr) => wtr.serialize(record)?,
+            RecordWriter::Json(out) => {
+                serde_json::to_writer(&mut *out, record)?;
+                writeln!(out)?;
+            }
         }
+        Ok(())
+    }
 
-        for iter in 0..NUM_PLAYERS * 2 {
-            let player = iter % NUM_PLAYERS;
-            players[player].add_card(cards[iter]);
+    fn flush(&mut self) -> Result<(), Box<dyn Error>> {
+        match self {
+            RecordWriter::Delimited(wtr) => wtr.flush()?,
+            RecordWriter::Json(out) => out.flush()?,
         }
+        Ok(())
+    }
+}
+
+/// The records and any verbose output for a run of consecutive trials.
+#[derive(Default)]
+struct Chunk {
+    records: Vec<Record>,
+    log: String,
+}
+
+/// Deals and scores trials on one worker thread, reusing its buffers between
+/// trials.
+struct Dealer {
+    game: Game,
+    strategies: Vec<Strategy>,
+    seed: u64,
+    verbose: bool,
+    deck: Deck,
+    players: Vec<Player>,
+    table_cards: Player,
+    strengths: Vec<u16>,
+    // Each player's share of the pot, and how much of it came from the low half.
+    shares: Vec<f64>,
+    low_shares: Vec<f64>,
+    drawn: Vec<usize>,
+}
 
-        for iter in NUM_PLAYERS * 2..NUM_PLAYERS * 2 + 5 {
-            table_cards.add_card(cards[iter])
+impl Dealer {
+    fn new(config: &Config) -> Dealer {
+        let players = (0..config.num_players)
+            .map(|i| Player::new(format!("Player {}", i + 1)))
+            .collect();
+
+        Dealer {
+            game: config.game,
+            strategies: config.strategies.clone(),
+            seed: config.seed,
+            verbose: config.verbosity == Verbosity::Verbose,
+            deck: config.game.deck(),
+            players,
+            table_cards: Player::new("Table".to_string()),
+            strengths: vec![],
+            shares: vec![],
+            low_shares: vec![],
+            drawn: vec![],
         }
+    }
 
-        // println!("{}", table_cards);
+    fn play(&mut self, trial_number: usize, chunk: &mut Chunk) {
+        // Always shuffle from a fresh deck so a trial only depends on its seed.
+        self.deck.reset();
+        self.deck.shuffle(&mut trial_rng(self.seed, trial_number));
 
-        for player in players.iter_mut() {
-            player.add_cards(table_cards.cards.clone());
+        for player in self.players.iter_mut() {
+            player.clear_cards();
         }
+        self.table_cards.clear_cards();
 
-        let mut winner_index = 0;
-        let mut best_hand = hand_result::HandResult::check_hand(&mut players[0].cards.clone());
+        let game = self.game;
+        self.drawn.clear();
 
-        for (i, player) in players.iter().enumerate() {
-            let player_hand = hand_result::HandResult::check_hand(&mut player.cards.clone());
-            // println!("{}", player);
-            // println!("{}\n", player_hand);
+        if let Game::Stud(stud_game) = game {
+            for &street in Street::ALL.iter() {
+                stud::deal_street(&mut self.deck, &mut self.players, street);
+            }
+
+            if self.verbose {
+                chunk.log += &format!(
+                    "Trial {}\nPlayer {} brings it in.\n\n",
+                    trial_number,
+                    stud::bring_in(stud_game, &self.players) + 1
+                );
+            }
+        } else if game == Game::Draw {
+            draw::deal(&mut self.deck, &mut self.players);
+
+            for (i, player) in self.players.iter_mut().enumerate() {
+                let strategy = &self.strategies[i % self.strategies.len()];
+                self.drawn
+                    .push(draw::draw(&mut self.deck, player, strategy).len());
+            }
 
-            if player_hand > best_hand {
-                // println!("Current best hand: {}", player_hand);
-                best_hand = player_hand;
-                winner_index = i;
+            if self.verbose {
+                chunk.log += &format!("Trial {}\n", trial_number);
+            }
+        } else {
+            for _ in 0..game.hole_cards() {
+                for player in self.players.iter_mut() {
+                    player.add_card(self.deck.deal_card().unwrap());
+                }
+            }
+
+            self.table_cards
+                .add_cards(self.deck.deal(BOARD_SIZE).unwrap());
+
+            if self.verbose {
+                chunk.log += &format!("Trial {}\n{}\n", trial_number, self.table_cards);
+            }
+
+            for player in self.players.iter_mut() {
+                for card in self.table_cards.cards.iter() {
+                    player.add_card(*card);
+                }
             }
         }
 
-        // println!(
-        //     "Best hand: {} for player:\n{}",
-        //     best_hand, players[winner_index]
-        // );
+        self.shares.clear();
+        self.low_shares.clear();
+
+        let split = match game {
+            Game::OmahaHiLo(n) => {
+                let holes = self
+                    .players
+                    .iter()
+                    .map(|player| player.cards[..n].to_vec())
+                    .collect::<Vec<Vec<Card>>>();
+                Some(omaha::hi_lo_shares(&holes, &self.table_cards.cards))
+            }
+            Game::Stud(stud_game) if stud_game != StudGame::Stud => {
+                let hands = self
+                    .players
+                    .iter()
+                    .map(|player| player.cards.clone())
+                    .collect::<Vec<Vec<Card>>>();
+                Some(stud::shares(stud_game, &hands))
+            }
+            _ => None,
+        };
+
+        if let Some(split) = split {
+            self.shares
+                .extend((0..self.players.len()).map(|i| split.total(i)));
+            self.low_shares.extend(split.low);
+        } else {
+            game.strengths(&self.players, &mut self.strengths);
+
+            let best = *self.strengths.iter().max().unwrap();
+
+            // Every player holding a hand equal to the best chops the pot.
+            let num_winners = self.strengths.iter().filter(|&&s| s == best).count();
+
+            self.shares.extend(self.strengths.iter().map(|&strength| {
+                if strength == best {
+                    1.0 / num_winners as f64
+                } else {
+                    0.0
+                }
+            }));
+            self.low_shares.resize(self.shares.len(), 0.0);
+        }
 
-        for (i, player) in players.iter().enumerate() {
-            let result_text;
-            if i == winner_index {
-                result_text = Some(format!("{}", best_hand));
+        for (i, player) in self.players.iter().enumerate() {
+            let share = self.shares[i];
+            let winner = share > 0.0;
+            let result_text = if winner {
+                let mut names = vec![];
+                if share > self.low_shares[i] {
+                    names.push(format!("{}", game.evaluate(&player.cards)));
+                }
+                if self.low_shares[i] > 0.0 {
+                    names.push(format!("{}", game.evaluate_low(&player.cards).unwrap()));
+                }
+                Some(names.join(" and "))
             } else {
-                result_text = None;
+                None
+            };
+
+            if winner && self.verbose {
+                chunk.log += &format!("Won with {}:\n{}\n", result_text.as_ref().unwrap(), player);
             }
-            wtr.serialize(Record {
+
+            chunk.records.push(Record {
                 player_number: i,
-                trial_number: trial_number,
+                trial_number,
+                seed: self.seed,
                 card_1: player.cards[0],
                 card_2: player.cards[1],
                 card_3: player.cards[2],
                 card_4: player.cards[3],
                 card_5: player.cards[4],
-                card_6: player.cards[5],
-                card_7: player.cards[6],
-                winner: i == winner_index,
+                card_6: player.cards.get(5).copied(),
+                card_7: player.cards.get(6).copied(),
+                card_8: player.cards.get(7).copied(),
+                card_9: player.cards.get(8).copied(),
+                card_10: player.cards.get(9).copied(),
+                card_11: player.cards.get(10).copied(),
+                winner,
+                share,
                 result_name: result_text,
-            })?;
+                drawn: self.drawn.get(i).copied(),
+            });
         }
     }
+}
+
+fn run(config: &Config) -> Result<(), Box<dyn Error>> {
+    let now = Instant::now();
+
+    let mut wtr = RecordWriter::new(&config.output, config.format)?;
+
+    if config.verbosity == Verbosity::Verbose {
+        eprintln!("Seed: {}", config.seed);
+    }
+
+    let (first_trial, num_trials) = match config.replay {
+        Some(trial_number) => (trial_number, 1),
+        None => (1, config.num_trials),
+    };
+
+    // Chunk `c` is dealt by worker `c % num_threads` and sent down that worker's
+    // channel, so reading the channels in turn writes the chunks in order. The
+    // output is the same whatever the number of threads, and the bounded channels
+    // stop fast workers from running too far ahead of the writer.
+    let num_chunks = num_trials.div_ceil(CHUNK_SIZE);
+    let num_threads = config.num_threads.min(num_chunks).max(1);
+
+    thread::scope(|scope| -> Result<(), Box<dyn Error>> {
+        let receivers = (0..num_threads)
+            .map(|worker| {
+                let (tx, rx) = mpsc::sync_channel::<Chunk>(CHUNKS_IN_FLIGHT);
+
+                scope.spawn(move || {
+                    let mut dealer = Dealer::new(config);
+
+                    for c in (worker..num_chunks).step_by(num_threads) {
+                        let start = first_trial + c * CHUNK_SIZE;
+                        let end = (start + CHUNK_SIZE).min(first_trial + num_trials);
+                        let mut chunk = Chunk::default();
+
+                        for trial_number in start..end {
+                            dealer.play(trial_number, &mut chunk);
+                        }
+
+                        // The writer has hung up after an error, so stop early.
+                        if tx.send(chunk).is_err() {
+                            return;
+                        }
+                    }
+                });
+
+                rx
+            })
+            .collect::<Vec<mpsc::Receiver<Chunk>>>();
+
+        for c in 0..num_chunks {
+            let chunk = receivers[c % num_threads].recv()?;
+
+            eprint!("{}", chunk.log);
+            for record in chunk.records.iter() {
+                wtr.write(record)?;
+            }
+        }
+
+        Ok(())
+    })?;
 
     wtr.flush()?;
 
-    println!("{}", now.elapsed().as_micros());
+    if config.verbosity >= Verbosity::Normal {
+        eprintln!("{}", now.elapsed().as_micros());
+    }
 
     Ok(())
 }
 
+fn equity(config: &EquityConfig) -> Result<(), Box<dyn Error>> {
+    let result =
+        equity::calculate_ranges(&config.ranges, &config.board, &config.dead, &config.options)?;
+    println!("{}", result);
+    Ok(())
+}
+
+fn outs(config: &OutsConfig) -> Result<(), Box<dyn Error>> {
+    let outs = outs::find_outs(&config.hand, &config.board, config.opponent.as_ref())?;
+    println!("{}", outs);
+    Ok(())
+}
+
 fn main() {
-    if let Err(err) = run() {
-        println!("{}", err);
+    let result = match Command::from_args() {
+        Command::Simulate(config) => run(&config),
+        Command::Equity(config) => equity(&config),
+        Command::Outs(config) => outs(&config),
+    };
+
+    if let Err(err) = result {
+        eprintln!("{}", err);
         process::exit(1);
     }
 }
+
+#[test]
+fn test_trial_rng_is_reproducible() {
+    let mut first = Deck::new();
+    let mut again = Deck::new();
+    let mut next_trial = Deck::new();
+
+    first.shuffle(&mut trial_rng(42, 7));
+    again.shuffle(&mut trial_rng(42, 7));
+    next_trial.shuffle(&mut trial_rng(42, 8));
+
+    assert_eq!(first.remaining(), again.remaining());
+    assert_ne!(first.remaining(), next_trial.remaining());
+}
@@ -0,0 +1,189 @@
+//! Omaha hand evaluation.
+//!
+//! Unlike Hold'em, an Omaha hand must be made from exactly two hole cards and
+//! exactly three board cards, so four hearts on the board with one in hand is
+//! no flush. Every way of picking two from the hand and three from the board is
+//! tried, and the best of them is the hand.
+
+use crate::card::*;
+use crate::evaluator;
+use crate::hand_result::HandResult;
+use crate::lowball::{self, HiLoShares, LowResult};
+
+pub const MIN_HOLE_CARDS: usize = 4;
+pub const MAX_HOLE_CARDS: usize = 6;
+const MIN_BOARD_CARDS: usize = 3;
+const MAX_BOARD_CARDS: usize = 5;
+
+/// Returns the strength of the best Omaha hand, on the same scale as
+/// `evaluator::strength`. `hole` must hold four to six cards and `board` three
+/// to five.
+pub fn strength(hole: &[Card], board: &[Card]) -> u16 {
+    let mut best = 0;
+    for_each_hand(hole, board, |hand| {
+        best = best.max(evaluator::strength(hand));
+    });
+    best
+}
+
+/// Returns the five cards, two from `hole` and three from `board`, making the
+/// best Omaha hand.
+pub fn best_five(hole: &[Card], board: &[Card]) -> Vec<Card> {
+    let mut best = (0, vec![]);
+    for_each_hand(hole, board, |hand| {
+        let strength = evaluator::strength(hand);
+        if strength > best.0 {
+            best = (strength, hand.to_vec());
+        }
+    });
+    best.1
+}
+
+/// Evaluates the best Omaha hand, with its cards taken from `best_five`.
+pub fn evaluate(hole: &[Card], board: &[Card]) -> HandResult {
+    HandResult::evaluate(&best_five(hole, board))
+}
+
+/// Returns the best eight or better ace-to-five low made from two cards in
+/// `hole` and three from `board`, if there is one.
+pub fn eight_or_better(hole: &[Card], board: &[Card]) -> Option<LowResult> {
+    let mut best: Option<LowResult> = None;
+    for_each_hand(hole, board, |hand| {
+        if let Some(low) = lowball::eight_or_better(hand) {
+            if best.as_ref().is_none_or(|best| low > *best) {
+                best = Some(low);
+            }
+        }
+    });
+    best
+}
+
+/// Splits the pot between Omaha Hi-Lo hands sharing `board`.
+pub fn hi_lo_shares(holes: &[Vec<Card>], board: &[Card]) -> HiLoShares {
+    let highs = holes
+        .iter()
+        .map(|hole| strength(hole, board))
+        .collect::<Vec<u16>>();
+    let lows = holes
+        .iter()
+        .map(|hole| eight_or_better(hole, board))
+        .collect::<Vec<Option<LowResult>>>();
+
+    HiLoShares::split(&highs, &lows)
+}
+
+/// Calls `f` with every hand of two cards from `hole` and three from `board`.
+fn for_each_hand(hole: &[Card], board: &[Card], mut f: impl FnMut(&[Card; 5])) {
+    assert!(
+        hole.len() >= MIN_HOLE_CARDS && hole.len() <= MAX_HOLE_CARDS,
+        "Omaha hands hold 4 to 6 cards, got {}.",
+        hole.len()
+    );
+    assert!(
+        board.len() >= MIN_BOARD_CARDS && board.len() <= MAX_BOARD_CARDS,
+        "Omaha boards hold 3 to 5 cards, got {}.",
+        board.len()
+    );
+
+    for (i, &first) in hole.iter().enumerate() {
+        for &second in hole[i + 1..].iter() {
+            for (j, &third) in board.iter().enumerate() {
+                for (k, &fourth) in board.iter().enumerate().skip(j + 1) {
+                    for &fifth in board[k + 1..].iter() {
+                        f(&[first, second, third, fourth, fifth]);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn test_omaha_uses_exactly_two_hole_cards() {
+    // One heart in hand with four on the board is no flush.
+    let hole = parse_hand("AhKs7c2d").unwrap();
+    let board = parse_hand("3h8h9hJh4s").unwrap();
+    assert_eq!(format!("{}", evaluate(&hole, &board)), "High Card");
+
+    // Four Aces in hand only ever play as a pair.
+    let hole = parse_hand("AhAsAdAc").unwrap();
+    let board = parse_hand("Kc7d2s").unwrap();
+    assert_eq!(format!("{}", evaluate(&hole, &board)), "Pair");
+}
+
+#[test]
+fn test_omaha_uses_exactly_three_board_cards() {
+    // The board's straight doesn't play, and AAKK can only use one of its pairs.
+    let hole = parse_hand("AhAsKdKc").unwrap();
+    let board = parse_hand("6c7d8s9hTc").unwrap();
+    let best = best_five(&hole, &board);
+
+    assert_eq!(format!("{}", evaluate(&hole, &board)), "Pair");
+    assert_eq!(best.iter().filter(|card| hole.contains(card)).count(), 2);
+    assert_eq!(best.iter().filter(|card| board.contains(card)).count(), 3);
+}
+
+#[test]
+fn test_omaha_low_uses_two_hole_cards() {
+    let board = parse_hand("2c3d4hKsKd").unwrap();
+
+    let low = eight_or_better(&parse_hand("Ah5sQcQd").unwrap(), &board).unwrap();
+    assert_eq!(format!("{}", low), "5-4-3-2-A Low");
+
+    // Only one low card in hand, so there's no low however good the board.
+    assert_eq!(
+        eight_or_better(&parse_hand("AhKhQcJd").unwrap(), &board),
+        None
+    );
+}
+
+#[test]
+fn test_omaha_hi_lo_shares() {
+    let board = parse_hand("2c3d4h9sKd").unwrap();
+    let holes = [
+        // Trip Kings take the high.
+        parse_hand("KcKhQsJs").unwrap(),
+        // 6-4-3-2-A takes the low.
+        parse_hand("Ah6sQcQd").unwrap(),
+        // A worse low, 8-7-4-3-2, and nothing for the high.
+        parse_hand("8h7cJhTd").unwrap(),
+    ];
+
+    let shares = hi_lo_shares(&holes, &board);
+    assert_eq!(shares.high, [0.5, 0.0, 0.0]);
+    assert_eq!(shares.low, [0.0, 0.5, 0.0]);
+
+    // With no low card on the board, the high hand scoops.
+    let board = parse_hand("9c9dTh9sKd").unwrap();
+    let shares = hi_lo_shares(&holes, &board);
+    assert_eq!(shares.high, [1.0, 0.0, 0.0]);
+    assert_eq!(shares.total(0), 1.0);
+}
+
+#[test]
+fn test_omaha_matches_check_hand_on_every_split() {
+    use rand::seq::SliceRandom;
+    use rand::thread_rng;
+
+    let mut rng = thread_rng();
+    let mut deck = Deck::new().remaining().to_vec();
+
+    for i in 0..3_000 {
+        deck.shuffle(&mut rng);
+        let num_hole = MIN_HOLE_CARDS + i % 3;
+        let num_board = MIN_BOARD_CARDS + i % 3;
+        let (hole, rest) = deck.split_at(num_hole);
+        let board = &rest[..num_board];
+
+        let mut expected = None;
+        for_each_hand(hole, board, |hand| {
+            let result = HandResult::check_hand(&mut hand.to_vec());
+            if expected.as_ref().is_none_or(|best| result > *best) {
+                expected = Some(result);
+            }
+        });
+
+        assert_eq!(Some(evaluate(hole, board)), expected);
+        assert_eq!(strength(hole, board), expected.unwrap().strength());
+    }
+}
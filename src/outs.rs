@@ -0,0 +1,276 @@
+//! Outs: the cards still to come that would help a Hold'em hand on the flop or
+//! turn, and the chance of catching one.
+
+use crate::card::*;
+use crate::hand_result::HandResult;
+
+use std::error::Error;
+use std::fmt;
+
+const FLOP: usize = 3;
+const TURN: usize = 4;
+const BOARD_SIZE: usize = 5;
+
+/// A card that helps the hand if it comes next.
+#[derive(Debug)]
+pub struct Out {
+    pub card: Card,
+    /// The hand made once the card comes.
+    pub result: HandResult,
+    /// Whether it moves the hand up a category, e.g. from a pair to two pair,
+    /// with the hole cards playing a part. A card that only pairs the board
+    /// helps every player just as much, so doesn't count.
+    pub improves: bool,
+    /// Whether it takes the hand from level with or behind the opponent to
+    /// ahead of them.
+    pub takes_lead: bool,
+}
+
+/// The chance of catching at least one of a set of outs.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Odds {
+    /// How many cards make up the set.
+    pub count: usize,
+    pub next_card: f64,
+    /// Equal to `next_card` on the turn.
+    pub by_river: f64,
+}
+
+#[derive(Debug)]
+pub struct Outs {
+    /// The hand made with the board as it is.
+    pub current: HandResult,
+    /// Every card that improves the hand or takes the lead, in deck order.
+    pub outs: Vec<Out>,
+    /// The cards that could still come, those not in a hand or on the board.
+    pub unseen: usize,
+    pub improve_odds: Odds,
+    /// Only worked out when there's an opponent to beat.
+    pub lead_odds: Option<Odds>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OutsError {
+    BoardSize(usize),
+    DuplicateCard(Card),
+}
+
+impl fmt::Display for OutsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutsError::BoardSize(n) => write!(
+                f,
+                "Outs need a flop or turn of {} or {} cards, got {}.",
+                FLOP, TURN, n
+            ),
+            OutsError::DuplicateCard(card) => {
+                write!(f, "The {} appears more than once.", card)
+            }
+        }
+    }
+}
+
+impl Error for OutsError {}
+
+/// Finds the outs for `hand` on a flop or turn `board`, optionally against an
+/// `opponent` whose hole cards are known.
+pub fn find_outs(
+    hand: &[Card; 2],
+    board: &[Card],
+    opponent: Option<&[Card; 2]>,
+) -> Result<Outs, OutsError> {
+    if board.len() != FLOP && board.len() != TURN {
+        return Err(OutsError::BoardSize(board.len()));
+    }
+
+    let mut seen = CardSet::new();
+    for card in hand
+        .iter()
+        .chain(board)
+        .chain(opponent.into_iter().flatten())
+    {
+        if !seen.insert(*card) {
+            return Err(OutsError::DuplicateCard(*card));
+        }
+    }
+
+    let with_board = |hole: &[Card; 2], extra: Option<Card>| {
+        let mut cards = hole.to_vec();
+        cards.extend_from_slice(board);
+        cards.extend(extra);
+        HandResult::check_hand(&mut cards)
+    };
+
+    let current = with_board(hand, None);
+    let behind = opponent.map(|opponent| current <= with_board(opponent, None));
+
+    let mut outs = vec![];
+    for card in (CardSet::full() - seen).iter() {
+        let result = with_board(hand, Some(card));
+        let mut board_only = board.to_vec();
+        board_only.push(card);
+        let board_only = HandResult::check_hand(&mut board_only);
+        let improves = result.category() > current.category()
+            && (result.made_cards().iter().any(|card| hand.contains(card))
+                || result.category() > board_only.category());
+        let takes_lead = behind == Some(true)
+            && opponent.is_some_and(|opponent| result > with_board(opponent, Some(card)));
+
+        if improves || takes_lead {
+            outs.push(Out {
+                card,
+                result,
+                improves,
+                takes_lead,
+            });
+        }
+    }
+
+    let unseen = CardSet::full().len() - seen.len();
+    let to_come = BOARD_SIZE - board.len();
+    let odds = |count| Odds {
+        count,
+        next_card: count as f64 / unseen as f64,
+        by_river: 1.0 - misses(unseen, count, to_come),
+    };
+
+    Ok(Outs {
+        improve_odds: odds(outs.iter().filter(|out| out.improves).count()),
+        lead_odds: opponent.map(|_| odds(outs.iter().filter(|out| out.takes_lead).count())),
+        current,
+        outs,
+        unseen,
+    })
+}
+
+/// The chance that none of `count` outs among `unseen` cards come in the next
+/// `to_come` cards.
+fn misses(unseen: usize, count: usize, to_come: usize) -> f64 {
+    (0..to_come)
+        .map(|i| (unseen - count - i) as f64 / (unseen - i) as f64)
+        .product()
+}
+
+impl fmt::Display for Outs {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Holding {}.", self.current)?;
+
+        for out in self.outs.iter() {
+            let mut reasons = vec![];
+            if out.improves {
+                reasons.push("improves");
+            }
+            if out.takes_lead {
+                reasons.push("takes the lead");
+            }
+            writeln!(f, "{}: {} ({})", out.card, out.result, reasons.join(", "))?;
+        }
+
+        let mut odds = vec![("improve", self.improve_odds)];
+        odds.extend(self.lead_odds.map(|lead_odds| ("take the lead", lead_odds)));
+
+        for (i, (name, odds)) in odds.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            write!(
+                f,
+                "{} outs to {} of {} unseen: {:.2}% next card, {:.2}% by the river.",
+                odds.count,
+                name,
+                self.unseen,
+                odds.next_card * 100.0,
+                odds.by_river * 100.0
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+fn hole_cards(text: &str) -> [Card; 2] {
+    let cards = parse_hand(text).unwrap();
+    [cards[0], cards[1]]
+}
+
+#[test]
+fn test_flush_and_straight_outs() {
+    // Nine hearts for the flush, plus the three other Tens and Fives for the
+    // straight, with the Ten and Five of Hearts already counted.
+    let outs = find_outs(&hole_cards("6h7h"), &parse_hand("8h9h2c").unwrap(), None).unwrap();
+
+    let flush_and_straight = outs
+        .outs
+        .iter()
+        .filter(|out| out.result.category() >= 4)
+        .count();
+
+    assert_eq!(outs.unseen, 47);
+    assert_eq!(flush_and_straight, 9 + 6);
+    assert!((outs.improve_odds.next_card - outs.improve_odds.count as f64 / 47.0).abs() < 1e-9);
+    assert!(outs.lead_odds.is_none());
+}
+
+#[test]
+fn test_board_pairing_cards_are_not_outs() {
+    // Nine hearts, six other Tens and Fives, and six Sixes and Sevens to pair
+    // a hole card. The eight other Eights, Nines and Twos only pair the board.
+    let outs = find_outs(&hole_cards("6h7h"), &parse_hand("8h9h2c").unwrap(), None).unwrap();
+
+    assert_eq!(outs.improve_odds.count, 9 + 6 + 6);
+    assert!(outs
+        .outs
+        .iter()
+        .all(
+            |out| ![Value::Eight, Value::Nine, Value::Two].contains(&out.card.value)
+                || out.card.suit == Suit::Hearts
+        ));
+}
+
+#[test]
+fn test_outs_against_an_opponent() {
+    // Two overcards against a pair: three Aces and three Kings take the lead.
+    let outs = find_outs(
+        &hole_cards("AhKd"),
+        &parse_hand("7c8s2d").unwrap(),
+        Some(&hole_cards("QsQd")),
+    )
+    .unwrap();
+
+    let lead = outs.lead_odds.unwrap();
+    assert_eq!(outs.unseen, 45);
+    assert_eq!(lead.count, 6);
+    assert!((lead.next_card - 6.0 / 45.0).abs() < 1e-9);
+    assert!((lead.by_river - (1.0 - 39.0 / 45.0 * 38.0 / 44.0)).abs() < 1e-9);
+    assert!(outs
+        .outs
+        .iter()
+        .filter(|out| out.takes_lead)
+        .all(|out| out.card.value == Value::Ace || out.card.value == Value::King));
+}
+
+#[test]
+fn test_no_lead_to_take_when_ahead() {
+    let outs = find_outs(
+        &hole_cards("QsQd"),
+        &parse_hand("7c8s2dJh").unwrap(),
+        Some(&hole_cards("AhKd")),
+    )
+    .unwrap();
+
+    assert_eq!(outs.lead_odds.unwrap().count, 0);
+    assert!((outs.improve_odds.next_card - outs.improve_odds.by_river).abs() < 1e-9);
+}
+
+#[test]
+fn test_outs_rejects_bad_input() {
+    assert_eq!(
+        find_outs(&hole_cards("AhKd"), &parse_hand("7c8s").unwrap(), None).unwrap_err(),
+        OutsError::BoardSize(2)
+    );
+    assert_eq!(
+        find_outs(&hole_cards("AhKd"), &parse_hand("7c8sAh").unwrap(), None).unwrap_err(),
+        OutsError::DuplicateCard(Card::new(Suit::Hearts, Value::Ace))
+    );
+}
@@ -2,21 +2,116 @@ use crate::card::Card;
 
 use std::fmt;
 
+/// Where a player stands in the hand being played.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Status {
+    /// Still able to bet.
+    #[default]
+    Active,
+    Folded,
+    /// In the hand with no chips left to bet.
+    AllIn,
+    /// Not dealt in, for want of chips.
+    SittingOut,
+}
+
 pub struct Player {
     pub cards: Vec<Card>,
+    /// Chips in front of the player, not counting any already bet.
+    pub stack: u64,
+    /// Chips put in on the current betting round.
+    pub bet: u64,
+    /// Chips put in over the current hand, antes and blinds included.
+    pub committed: u64,
+    pub status: Status,
+    /// Where the player sits at the table, numbered from 0.
+    pub seat: usize,
+    // Whether each of `cards` was dealt face up, as in stud. Cards added with
+    // `add_card` are dealt face down.
+    face_up: Vec<bool>,
     name: String,
 }
 
 impl Player {
     pub fn new(name: String) -> Self {
+        Player::with_stack(name, 0)
+    }
+
+    pub fn with_stack(name: String, stack: u64) -> Self {
         Player {
             cards: Vec::<Card>::new(),
-            name: name,
+            stack,
+            bet: 0,
+            committed: 0,
+            status: Status::Active,
+            seat: 0,
+            face_up: vec![],
+            name,
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Clears the last hand away, dealing the player in if they have chips.
+    pub fn new_hand(&mut self) {
+        self.clear_cards();
+        self.bet = 0;
+        self.committed = 0;
+        self.status = if self.stack > 0 {
+            Status::Active
+        } else {
+            Status::SittingOut
+        };
+    }
+
+    /// Moves chips from the stack into the pot, as many as the player has,
+    /// going all in if that's everything. Returns the chips put in.
+    pub fn put_in(&mut self, amount: u64) -> u64 {
+        let amount = amount.min(self.stack);
+
+        self.stack -= amount;
+        self.bet += amount;
+        self.committed += amount;
+        if self.stack == 0 && self.status == Status::Active {
+            self.status = Status::AllIn;
         }
+
+        amount
+    }
+
+    pub fn fold(&mut self) {
+        self.status = Status::Folded;
+    }
+
+    pub fn is_folded(&self) -> bool {
+        self.status == Status::Folded
+    }
+
+    pub fn is_all_in(&self) -> bool {
+        self.status == Status::AllIn
+    }
+
+    /// Whether the player was dealt in and hasn't folded.
+    pub fn in_hand(&self) -> bool {
+        self.status == Status::Active || self.status == Status::AllIn
+    }
+
+    /// Whether the player is in the hand with chips left to bet.
+    pub fn can_act(&self) -> bool {
+        self.status == Status::Active
     }
 
     pub fn add_card(&mut self, card: Card) {
         self.cards.push(card);
+        self.face_up.push(false);
+    }
+
+    /// Adds a card dealt face up, for every player to see.
+    pub fn add_up_card(&mut self, card: Card) {
+        self.cards.push(card);
+        self.face_up.push(true);
     }
 
     pub fn add_cards(&mut self, cards: Vec<Card>) {
@@ -24,14 +119,94 @@ impl Player {
             self.add_card(card);
         }
     }
+
+    /// Takes `cards` out of the hand, such as when discarding in draw poker.
+    /// Cards not held are ignored.
+    pub fn remove_cards(&mut self, cards: &[Card]) {
+        let mut face_up = self.face_up.iter();
+        let mut kept_face_up = vec![];
+
+        self.cards.retain(|card| {
+            let up = face_up.next().copied().unwrap_or(false);
+            let keep = !cards.contains(card);
+            if keep {
+                kept_face_up.push(up);
+            }
+            keep
+        });
+
+        self.face_up = kept_face_up;
+    }
+
+    /// Empties the hand, ready for the next deal.
+    pub fn clear_cards(&mut self) {
+        self.cards.clear();
+        self.face_up.clear();
+    }
+
+    /// The cards dealt face up, in the order they were dealt.
+    pub fn up_cards(&self) -> Vec<Card> {
+        self.cards_facing(true)
+    }
+
+    /// The cards dealt face down, in the order they were dealt.
+    pub fn down_cards(&self) -> Vec<Card> {
+        self.cards_facing(false)
+    }
+
+    pub fn is_face_up(&self, index: usize) -> bool {
+        self.face_up.get(index).copied().unwrap_or(false)
+    }
+
+    fn cards_facing(&self, up: bool) -> Vec<Card> {
+        self.cards
+            .iter()
+            .zip(self.face_up.iter())
+            .filter(|&(_, &face_up)| face_up == up)
+            .map(|(&card, _)| card)
+            .collect()
+    }
 }
 
 impl fmt::Display for Player {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         writeln!(f, "{} has:", self.name)?;
-        for card in &self.cards {
-            writeln!(f, "\t- {}", card)?;
+        for (i, card) in self.cards.iter().enumerate() {
+            if self.is_face_up(i) {
+                writeln!(f, "\t- {} (up)", card)?;
+            } else {
+                writeln!(f, "\t- {}", card)?;
+            }
         }
         Ok(())
     }
 }
+
+#[test]
+fn test_put_in_and_new_hand() {
+    use crate::card::*;
+
+    let mut player = Player::with_stack("Player 1".to_string(), 150);
+    player.add_card(Card::new(Suit::Spades, Value::Ace));
+
+    assert_eq!(player.put_in(100), 100);
+    assert!(player.can_act());
+    player.bet = 0;
+    // Only the 50 left can go in, which puts the player all in.
+    assert_eq!(player.put_in(100), 50);
+    assert_eq!((player.stack, player.bet, player.committed), (0, 50, 150));
+    assert!(player.is_all_in());
+    assert!(player.in_hand());
+
+    player.new_hand();
+    assert!(player.cards.is_empty());
+    assert_eq!((player.bet, player.committed), (0, 0));
+    assert_eq!(player.status, Status::SittingOut);
+    assert!(!player.in_hand());
+
+    player.stack = 100;
+    player.new_hand();
+    player.fold();
+    assert!(player.is_folded());
+    assert!(!player.can_act());
+}
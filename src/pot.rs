@@ -0,0 +1,390 @@
+//! Splitting the pot into a main pot and side pots when players are all in
+//! for different amounts, and awarding each to the best hand that can win it.
+//!
+//! Each pot takes the same amount from everyone who put in at least as much,
+//! so a player all in for 100 can only win 100 from each opponent. Players
+//! who folded leave their chips in the pots without being able to win them.
+//! Chips nobody still in the hand matched, such as an uncalled bet, make up a
+//! pot of their own that goes straight back to the one player who can win it.
+
+use crate::card::Card;
+
+/// A main or side pot.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pot {
+    pub amount: u64,
+    /// The seats that can win it, in seat order.
+    pub eligible: Vec<usize>,
+}
+
+/// Who gets the chips left over when a pot doesn't split evenly.
+#[derive(Debug, Clone, Copy)]
+pub enum OddChips<'a> {
+    /// The first winners round the table from the button, as in Hold'em and
+    /// Omaha.
+    LeftOfButton(usize),
+    /// The winners holding the highest card, with Spades beating Hearts,
+    /// Diamonds then Clubs on the same value, as in stud. Takes each seat's
+    /// cards.
+    HighestCard(&'a [Vec<Card>]),
+}
+
+/// Builds the main pot and any side pots from the chips each seat put in over
+/// the hand, and whether each seat is still in it. The main pot comes first.
+pub fn pots(committed: &[u64], in_hand: &[bool]) -> Vec<Pot> {
+    assert_eq!(committed.len(), in_hand.len());
+
+    let mut levels = committed
+        .iter()
+        .cloned()
+        .filter(|&committed| committed > 0)
+        .collect::<Vec<u64>>();
+    levels.sort_unstable();
+    levels.dedup();
+
+    let mut pots: Vec<Pot> = vec![];
+    let mut below = 0;
+    for level in levels {
+        let contributors = committed.iter().filter(|&&c| c >= level).count() as u64;
+        let amount = contributors * (level - below);
+        let eligible = (0..committed.len())
+            .filter(|&i| in_hand[i] && committed[i] >= level)
+            .collect::<Vec<usize>>();
+        below = level;
+
+        // Chips from players who put in more and then folded go to the pot
+        // below, as nobody left can win them on their own, and so do chips
+        // the same players are eligible for.
+        match pots.last_mut() {
+            Some(last) if eligible.is_empty() || eligible == last.eligible => last.amount += amount,
+            _ if eligible.is_empty() => pots.push(Pot {
+                amount,
+                eligible: vec![],
+            }),
+            _ => pots.push(Pot { amount, eligible }),
+        }
+    }
+
+    pots
+}
+
+/// Awards each pot to its eligible seats holding the best of `hands`, which
+/// are indexed by seat. Returns the chips each seat wins, which add up to
+/// every chip in the pots.
+///
+/// Panics if nobody is eligible for a pot, which only happens when nobody is
+/// left in the hand to win it.
+pub fn award<H: Ord>(pots: &[Pot], hands: &[H], odd_chips: OddChips) -> Vec<u64> {
+    let mut winnings = vec![0; hands.len()];
+
+    for pot in pots {
+        let best = pot
+            .eligible
+            .iter()
+            .map(|&i| &hands[i])
+            .max()
+            .expect("Nobody is left in the hand to win the pot.");
+        let mut winners = pot
+            .eligible
+            .iter()
+            .cloned()
+            .filter(|&i| hands[i] == *best)
+            .collect::<Vec<usize>>();
+
+        match odd_chips {
+            OddChips::LeftOfButton(button) => {
+                let n = hands.len();
+                winners.sort_by_key(|&i| (i + n - button - 1) % n);
+            }
+            OddChips::HighestCard(cards) => {
+                winners.sort_by_key(|&i| {
+                    std::cmp::Reverse(
+                        cards[i]
+                            .iter()
+                            .map(|card| (card.value.value(), card.suit.index()))
+                            .max(),
+                    )
+                });
+            }
+        }
+
+        let share = pot.amount / winners.len() as u64;
+        let odd = (pot.amount % winners.len() as u64) as usize;
+        for (n, &i) in winners.iter().enumerate() {
+            winnings[i] += share + if n < odd { 1 } else { 0 };
+        }
+    }
+
+    winnings
+}
+
+/// Builds the pots and awards them, returning the chips each seat wins.
+///
+/// Panics if nobody is still in the hand.
+pub fn settle<H: Ord>(
+    committed: &[u64],
+    in_hand: &[bool],
+    hands: &[H],
+    odd_chips: OddChips,
+) -> Vec<u64> {
+    award(&pots(committed, in_hand), hands, odd_chips)
+}
+
+/// Works each seat's winnings out a chip at a time rather than a pot at a
+/// time: the chip each player puts in at every level goes to the best hand
+/// among the players still in who put in at least that much, or if there are
+/// none, among those still in who put in the most. Shares of split chips are
+/// kept as fractions.
+#[cfg(test)]
+fn reference(committed: &[u64], in_hand: &[bool], hands: &[u32]) -> Vec<f64> {
+    let mut winnings = vec![0.0; hands.len()];
+    let seats = 0..hands.len();
+    let most_in = seats
+        .clone()
+        .filter(|&i| in_hand[i])
+        .map(|i| committed[i])
+        .max()
+        .unwrap_or(0);
+
+    for level in 1..=committed.iter().cloned().max().unwrap_or(0) {
+        let floor = level.min(most_in);
+        let eligible = |i: &usize| in_hand[*i] && committed[*i] >= floor;
+        let best = match seats.clone().filter(eligible).map(|i| hands[i]).max() {
+            Some(best) => best,
+            None => continue,
+        };
+        let num_winners = seats
+            .clone()
+            .filter(eligible)
+            .filter(|&i| hands[i] == best)
+            .count();
+        let chips = committed.iter().filter(|&&c| c >= level).count() as f64;
+
+        for i in seats.clone().filter(eligible).filter(|&i| hands[i] == best) {
+            winnings[i] += chips / num_winners as f64;
+        }
+    }
+
+    winnings
+}
+
+/// Checks `settle` against `reference` for every way of ranking the hands,
+/// ties included, and every button position. Each seat must be within an odd
+/// chip per pot of its exact share, with every chip handed out.
+#[cfg(test)]
+fn check_every_ranking(committed: &[u64], in_hand: &[bool]) {
+    let n = committed.len();
+    let total = committed.iter().sum::<u64>();
+    let num_pots = pots(committed, in_hand).len() as f64;
+    let mut hands = vec![0u32; n];
+
+    loop {
+        let expected = reference(committed, in_hand, &hands);
+        for button in 0..n {
+            let won = settle(committed, in_hand, &hands, OddChips::LeftOfButton(button));
+
+            assert_eq!(
+                won.iter().sum::<u64>(),
+                total,
+                "{:?} {:?}",
+                committed,
+                hands
+            );
+            for i in 0..n {
+                assert!(
+                    (won[i] as f64 - expected[i]).abs() < num_pots,
+                    "seat {} won {} not {} with {:?} {:?} {:?}",
+                    i,
+                    won[i],
+                    expected[i],
+                    committed,
+                    in_hand,
+                    hands
+                );
+                if !in_hand[i] {
+                    assert_eq!(won[i], 0);
+                }
+            }
+        }
+
+        // Count through every ranking, each hand from 0 to n - 1.
+        let mut i = 0;
+        while i < n && hands[i] as usize == n - 1 {
+            hands[i] = 0;
+            i += 1;
+        }
+        if i == n {
+            break;
+        }
+        hands[i] += 1;
+    }
+}
+
+#[test]
+fn test_three_way_all_in() {
+    // Short stack all in for 100, a middle stack for 300, a big stack covers.
+    let committed = [100, 300, 500];
+    let in_hand = [true, true, true];
+
+    assert_eq!(
+        pots(&committed, &in_hand),
+        vec![
+            Pot {
+                amount: 300,
+                eligible: vec![0, 1, 2],
+            },
+            Pot {
+                amount: 400,
+                eligible: vec![1, 2],
+            },
+            Pot {
+                amount: 200,
+                eligible: vec![2],
+            },
+        ]
+    );
+
+    // The short stack wins the main pot, the middle stack the side pot, and
+    // the big stack gets back the 200 nobody called.
+    assert_eq!(
+        settle(&committed, &in_hand, &[3, 2, 1], OddChips::LeftOfButton(0)),
+        [300, 400, 200]
+    );
+    // The big stack scoops.
+    assert_eq!(
+        settle(&committed, &in_hand, &[1, 2, 3], OddChips::LeftOfButton(0)),
+        [0, 0, 900]
+    );
+
+    check_every_ranking(&[100, 300, 500], &[true, true, true]);
+    check_every_ranking(&[100, 100, 500], &[true, true, true]);
+    check_every_ranking(&[250, 250, 250], &[true, true, true]);
+    check_every_ranking(&[101, 333, 333], &[true, true, true]);
+    check_every_ranking(&[200, 300, 500], &[false, true, true]);
+    check_every_ranking(&[500, 300, 100], &[false, true, true]);
+}
+
+#[test]
+fn test_four_way_all_in() {
+    let committed = [50, 200, 200, 400];
+    let in_hand = [true, true, true, true];
+
+    assert_eq!(
+        pots(&committed, &in_hand)
+            .iter()
+            .map(|pot| pot.amount)
+            .collect::<Vec<u64>>(),
+        [200, 450, 200]
+    );
+
+    // Seats 1 and 2 tie, splitting the main pot and the first side pot.
+    assert_eq!(
+        settle(
+            &committed,
+            &in_hand,
+            &[1, 5, 5, 2],
+            OddChips::LeftOfButton(3)
+        ),
+        [0, 325, 325, 200]
+    );
+
+    // A three way tie for the main pot of 200 leaves two odd chips, which go
+    // to the first two winners after the button.
+    let committed = [50, 50, 50, 50];
+    let won = settle(
+        &committed,
+        &in_hand,
+        &[4, 4, 0, 4],
+        OddChips::LeftOfButton(0),
+    );
+    assert_eq!(won, [66, 67, 0, 67]);
+    let won = settle(
+        &committed,
+        &in_hand,
+        &[4, 4, 0, 4],
+        OddChips::LeftOfButton(1),
+    );
+    assert_eq!(won, [67, 66, 0, 67]);
+
+    check_every_ranking(&[50, 200, 200, 400], &[true, true, true, true]);
+    check_every_ranking(&[10, 20, 30, 40], &[true, true, true, true]);
+    check_every_ranking(&[100, 100, 100, 100], &[true, true, true, true]);
+    check_every_ranking(&[33, 67, 67, 100], &[true, true, true, true]);
+    check_every_ranking(&[400, 150, 75, 400], &[true, false, true, true]);
+    check_every_ranking(&[10, 500, 500, 20], &[true, true, false, false]);
+}
+
+#[test]
+fn test_six_way_all_in() {
+    let committed = [25, 100, 100, 300, 600, 1000];
+    let in_hand = [true; 6];
+
+    assert_eq!(
+        pots(&committed, &in_hand)
+            .iter()
+            .map(|pot| (pot.amount, pot.eligible.len()))
+            .collect::<Vec<(u64, usize)>>(),
+        [(150, 6), (375, 5), (600, 3), (600, 2), (400, 1)]
+    );
+
+    // Each player has a better hand than anyone with a bigger stack, so wins
+    // the pot they can cover, except seat 2 which is level with the better
+    // seat 1. The big stack only gets its uncalled chips back.
+    assert_eq!(
+        settle(
+            &committed,
+            &in_hand,
+            &[6, 5, 4, 3, 2, 1],
+            OddChips::LeftOfButton(0)
+        ),
+        [150, 375, 0, 600, 600, 400]
+    );
+
+    check_every_ranking(&[5, 20, 20, 60, 120, 200], &[true; 6]);
+    check_every_ranking(&[7, 13, 29, 29, 51, 97], &[true; 6]);
+    check_every_ranking(&[100, 100, 100, 100, 100, 100], &[true; 6]);
+    check_every_ranking(
+        &[10, 300, 300, 40, 300, 5],
+        &[true, false, true, true, true, false],
+    );
+}
+
+#[test]
+fn test_folded_chips_stay_in_the_pot() {
+    // The big blind folds to an all in for less, so their extra chips join
+    // the main pot rather than making a pot nobody can win.
+    let committed = [10, 40, 100];
+    let in_hand = [false, true, false];
+
+    assert_eq!(
+        pots(&committed, &in_hand),
+        vec![Pot {
+            amount: 150,
+            eligible: vec![1],
+        }]
+    );
+    assert_eq!(
+        settle(&committed, &in_hand, &[0, 0, 0], OddChips::LeftOfButton(0)),
+        [0, 150, 0]
+    );
+}
+
+#[test]
+fn test_odd_chip_to_highest_card() {
+    use crate::card::parse_hand;
+
+    let cards = vec![
+        parse_hand("AhKd").unwrap(),
+        parse_hand("AsQc").unwrap(),
+        parse_hand("2c3c").unwrap(),
+    ];
+    let won = settle(
+        &[101, 101, 101],
+        &[true, true, true],
+        &[1, 1, 0],
+        OddChips::HighestCard(&cards),
+    );
+
+    // The Ace of Spades beats the Ace of Hearts to the odd chip.
+    assert_eq!(won, [151, 152, 0]);
+}
@@ -0,0 +1,405 @@
+//! Ranges of Hold'em starting hands, written the usual shorthand way.
+//!
+//! A range is a comma separated list of:
+//!
+//! - pairs, "QQ", every pair from one up, "QQ+", or a span of them, "QQ-99";
+//! - two ranks, suited "AKs", offsuit "AKo" or either "AK";
+//! - a kicker run, "A9s+" for A9s up to AKs, or "A5s-A2s";
+//! - connectors moving up together, "76s+" for 76s, 87s and so on up to AKs,
+//!   or "T9s-54s";
+//! - exact hole cards, "AhKh".
+//!
+//! Any of these may end in ":weight" to hold those combos only some of the
+//! time, e.g. "AKo:0.5".
+
+use crate::card::*;
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+const NUM_RANKS: u8 = 13;
+
+/// Two exact hole cards and how often they're held, from 0 to 1.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Combo {
+    pub cards: [Card; 2],
+    pub weight: f64,
+}
+
+impl Combo {
+    pub fn card_set(&self) -> CardSet {
+        CardSet::from(&self.cards[..])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Range {
+    combos: Vec<Combo>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ParseRangeError {
+    Empty,
+    InvalidHand(String),
+    InvalidWeight(String),
+    InvalidSpan(String),
+}
+
+impl fmt::Display for ParseRangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParseRangeError::Empty => write!(f, "Expected a range but found nothing."),
+            ParseRangeError::InvalidHand(text) => write!(
+                f,
+                "'{}' is not a hand, expected something like QQ, AKs, KQo or AhKh.",
+                text
+            ),
+            ParseRangeError::InvalidWeight(text) => {
+                write!(f, "'{}' is not a weight between 0 and 1.", text)
+            }
+            ParseRangeError::InvalidSpan(text) => write!(
+                f,
+                "'{}' is not a span, both ends must be pairs, share a top card or have the same gap.",
+                text
+            ),
+        }
+    }
+}
+
+impl Error for ParseRangeError {}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Suitedness {
+    Pair,
+    Suited,
+    Offsuit,
+    Either,
+}
+
+/// A starting hand ignoring suits, such as AKs, with ranks as in `Value::rank`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct HandClass {
+    high: u8,
+    low: u8,
+    suitedness: Suitedness,
+}
+
+impl HandClass {
+    fn parse(text: &str) -> Result<HandClass, ParseRangeError> {
+        let invalid = || ParseRangeError::InvalidHand(text.to_string());
+        let chars = text.chars().collect::<Vec<char>>();
+
+        if chars.len() < 2 || chars.len() > 3 {
+            return Err(invalid());
+        }
+
+        let rank = |c: char| {
+            c.to_string()
+                .parse::<Value>()
+                .map(|value| value.rank())
+                .map_err(|_| invalid())
+        };
+        let (first, second) = (rank(chars[0])?, rank(chars[1])?);
+
+        let suitedness = match (first == second, chars.get(2)) {
+            (true, None) => Suitedness::Pair,
+            (true, Some(_)) => return Err(invalid()),
+            (false, None) => Suitedness::Either,
+            (false, Some('s')) | (false, Some('S')) => Suitedness::Suited,
+            (false, Some('o')) | (false, Some('O')) => Suitedness::Offsuit,
+            _ => return Err(invalid()),
+        };
+
+        Ok(HandClass {
+            high: first.max(second),
+            low: first.min(second),
+            suitedness,
+        })
+    }
+
+    fn with_ranks(&self, high: u8, low: u8) -> HandClass {
+        HandClass {
+            high,
+            low,
+            suitedness: self.suitedness,
+        }
+    }
+
+    /// Every class from this one up: pairs up to Aces, kickers up to one below
+    /// the top card, and connectors moving up together until the top card is an
+    /// Ace.
+    fn and_up(&self) -> Vec<HandClass> {
+        if self.suitedness == Suitedness::Pair {
+            (self.high..NUM_RANKS)
+                .map(|rank| self.with_ranks(rank, rank))
+                .collect()
+        } else if self.low + 1 < self.high {
+            (self.low..self.high)
+                .map(|low| self.with_ranks(self.high, low))
+                .collect()
+        } else {
+            (0..NUM_RANKS - self.high)
+                .map(|step| self.with_ranks(self.high + step, self.low + step))
+                .collect()
+        }
+    }
+
+    /// Every class between this one and `other`, inclusive.
+    fn span(&self, other: &HandClass) -> Option<Vec<HandClass>> {
+        if self.suitedness != other.suitedness {
+            return None;
+        }
+
+        let (bottom, top) = if self.high <= other.high && self.low <= other.low {
+            (self, other)
+        } else {
+            (other, self)
+        };
+
+        if self.suitedness == Suitedness::Pair {
+            Some(
+                (bottom.high..=top.high)
+                    .map(|rank| self.with_ranks(rank, rank))
+                    .collect(),
+            )
+        } else if bottom.high == top.high {
+            Some(
+                (bottom.low..=top.low)
+                    .map(|low| self.with_ranks(top.high, low))
+                    .collect(),
+            )
+        } else if bottom.high - bottom.low == top.high - top.low {
+            Some(
+                (0..=top.high - bottom.high)
+                    .map(|step| self.with_ranks(bottom.high + step, bottom.low + step))
+                    .collect(),
+            )
+        } else {
+            None
+        }
+    }
+
+    fn combos(&self, weight: f64) -> Vec<Combo> {
+        let (high, low) = (Value::from_rank(self.high), Value::from_rank(self.low));
+        let mut combos = vec![];
+
+        for i in 0..4 {
+            for j in 0..4 {
+                let keep = match self.suitedness {
+                    Suitedness::Pair => i < j,
+                    Suitedness::Suited => i == j,
+                    Suitedness::Offsuit => i != j,
+                    Suitedness::Either => true,
+                };
+
+                if keep {
+                    combos.push(Combo {
+                        cards: [
+                            Card::new(Suit::from_index(i), high),
+                            Card::new(Suit::from_index(j), low),
+                        ],
+                        weight,
+                    });
+                }
+            }
+        }
+
+        combos
+    }
+}
+
+impl Range {
+    pub fn new() -> Range {
+        Range { combos: vec![] }
+    }
+
+    pub fn combos(&self) -> &[Combo] {
+        &self.combos
+    }
+
+    pub fn len(&self) -> usize {
+        self.combos.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.combos.is_empty()
+    }
+
+    /// Adds a combo, replacing the weight of the same hole cards if already held.
+    pub fn insert(&mut self, combo: Combo) {
+        let cards = combo.card_set();
+
+        match self.combos.iter_mut().find(|held| held.card_set() == cards) {
+            Some(held) => held.weight = combo.weight,
+            None => self.combos.push(combo),
+        }
+    }
+
+    /// Returns the combos that don't use any of `dead`, such as cards already on
+    /// the board or in another player's hand.
+    pub fn without(&self, dead: CardSet) -> Range {
+        Range {
+            combos: self
+                .combos
+                .iter()
+                .filter(|combo| (combo.card_set() & dead).is_empty())
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// The number of combos, counting each by its weight.
+    pub fn total_weight(&self) -> f64 {
+        self.combos.iter().map(|combo| combo.weight).sum()
+    }
+}
+
+impl From<[Card; 2]> for Range {
+    fn from(cards: [Card; 2]) -> Range {
+        Range {
+            combos: vec![Combo { cards, weight: 1.0 }],
+        }
+    }
+}
+
+impl FromStr for Range {
+    type Err = ParseRangeError;
+
+    /// Parses a range such as "QQ+, AKs, A5s-A2s, KQo, 76s+". A hand listed
+    /// more than once takes the last weight given.
+    fn from_str(text: &str) -> Result<Range, ParseRangeError> {
+        let mut range = Range::new();
+
+        for token in text.split(',').map(str::trim) {
+            if token.is_empty() {
+                continue;
+            }
+
+            for combo in parse_token(token)? {
+                range.insert(combo);
+            }
+        }
+
+        if range.is_empty() {
+            return Err(ParseRangeError::Empty);
+        }
+
+        Ok(range)
+    }
+}
+
+fn parse_token(token: &str) -> Result<Vec<Combo>, ParseRangeError> {
+    let (hands, weight) = match token.split_once(':') {
+        Some((hands, weight)) => {
+            let invalid = || ParseRangeError::InvalidWeight(weight.to_string());
+            let weight = weight.trim().parse::<f64>().map_err(|_| invalid())?;
+            if !(0.0..=1.0).contains(&weight) {
+                return Err(invalid());
+            }
+            (hands.trim(), weight)
+        }
+        None => (token, 1.0),
+    };
+
+    if let Ok(cards) = parse_hand(hands) {
+        return match cards[..] {
+            [first, second] => Ok(vec![Combo {
+                cards: [first, second],
+                weight,
+            }]),
+            _ => Err(ParseRangeError::InvalidHand(hands.to_string())),
+        };
+    }
+
+    let classes = if let Some((from, to)) = hands.split_once('-') {
+        HandClass::parse(from.trim())?
+            .span(&HandClass::parse(to.trim())?)
+            .ok_or_else(|| ParseRangeError::InvalidSpan(hands.to_string()))?
+    } else if let Some(hand) = hands.strip_suffix('+') {
+        HandClass::parse(hand)?.and_up()
+    } else {
+        vec![HandClass::parse(hands)?]
+    };
+
+    Ok(classes
+        .iter()
+        .flat_map(|class| class.combos(weight))
+        .collect())
+}
+
+#[cfg(test)]
+fn combo_bits(text: &str) -> Vec<u64> {
+    let range = text.parse::<Range>().unwrap();
+    let mut bits = range
+        .combos()
+        .iter()
+        .map(|combo| combo.card_set().bits())
+        .collect::<Vec<u64>>();
+    bits.sort_unstable();
+    bits
+}
+
+#[test]
+fn test_range_combo_counts() {
+    let count = |text: &str| text.parse::<Range>().unwrap().len();
+
+    assert_eq!(count("QQ"), 6);
+    assert_eq!(count("AKs"), 4);
+    assert_eq!(count("AKo"), 12);
+    assert_eq!(count("AK"), 16);
+    assert_eq!(count("QQ+"), 18);
+    assert_eq!(count("22+"), 78);
+    // 76s+ runs up to AKs, which is only counted once.
+    assert_eq!(count("QQ+, AKs, A5s-A2s, KQo, 76s+"), 18 + 4 + 16 + 12 + 28);
+    assert_eq!(count("AhKh, AKs"), 4);
+    assert_eq!(count("AA, KK, QQ, KK+"), 18);
+}
+
+#[test]
+fn test_range_spans() {
+    assert_eq!(combo_bits("A9s+"), combo_bits("A9s, ATs, AJs, AQs, AKs"));
+    assert_eq!(combo_bits("KA"), combo_bits("AK"));
+    assert_eq!(
+        combo_bits("76s+"),
+        combo_bits("76s, 87s, 98s, T9s, JTs, QJs, KQs, AKs")
+    );
+    assert_eq!(combo_bits("A5s-A2s"), combo_bits("A2s, A3s, A4s, A5s"));
+    assert_eq!(combo_bits("QQ-99"), combo_bits("99, TT, JJ, QQ"));
+    assert_eq!(
+        combo_bits("T9o-54o"),
+        combo_bits("54o, 65o, 76o, 87o, 98o, T9o")
+    );
+}
+
+#[test]
+fn test_range_weights_and_card_removal() {
+    let range = "AA, AKs:0.5".parse::<Range>().unwrap();
+    assert_eq!(range.total_weight(), 6.0 + 2.0);
+
+    let board = CardSet::from(&parse_hand("As7c2d").unwrap()[..]);
+    let live = range.without(board);
+    assert_eq!(live.len(), 3 + 3);
+    assert_eq!(live.total_weight(), 3.0 + 1.5);
+}
+
+#[test]
+fn test_range_rejects_bad_notation() {
+    assert_eq!("".parse::<Range>(), Err(ParseRangeError::Empty));
+    assert_eq!(
+        "QQs".parse::<Range>(),
+        Err(ParseRangeError::InvalidHand("QQs".to_string()))
+    );
+    assert_eq!(
+        "AK:2".parse::<Range>(),
+        Err(ParseRangeError::InvalidWeight("2".to_string()))
+    );
+    assert_eq!(
+        "AKs-QJo".parse::<Range>(),
+        Err(ParseRangeError::InvalidSpan("AKs-QJo".to_string()))
+    );
+    assert_eq!(
+        "AhKhQh".parse::<Range>(),
+        Err(ParseRangeError::InvalidHand("AhKhQh".to_string()))
+    );
+}
@@ -0,0 +1,227 @@
+//! Seven card stud and Razz dealing.
+//!
+//! There's no board, each player is dealt seven cards of their own over five
+//! streets: two down and one up on third street, one up on each of fourth,
+//! fifth and sixth, and the last one down on seventh street. The player
+//! showing the worst card on third street pays the bring-in.
+
+use crate::card::*;
+use crate::evaluator;
+use crate::lowball::{self, HiLoShares, LowResult};
+use crate::player::Player;
+
+/// Seven cards each for up to seven players takes 49 cards, so the deck never
+/// runs short.
+pub const MAX_PLAYERS: usize = 7;
+pub const HAND_SIZE: usize = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StudGame {
+    /// The best high hand wins.
+    Stud,
+    /// The pot is split between the best high hand and the best eight or
+    /// better low.
+    StudHiLo,
+    /// The best ace-to-five low wins.
+    Razz,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Street {
+    Third,
+    Fourth,
+    Fifth,
+    Sixth,
+    Seventh,
+}
+
+impl Street {
+    pub const ALL: [Street; 5] = [
+        Street::Third,
+        Street::Fourth,
+        Street::Fifth,
+        Street::Sixth,
+        Street::Seventh,
+    ];
+
+    /// Whether each card dealt to a player on this street is face up, in the
+    /// order they're dealt.
+    pub fn face_up(&self) -> &'static [bool] {
+        match self {
+            Street::Third => &[false, false, true],
+            Street::Fourth | Street::Fifth | Street::Sixth => &[true],
+            Street::Seventh => &[false],
+        }
+    }
+
+    /// The cards a player was dealt on this street, if it has been dealt.
+    pub fn cards<'a>(&self, player: &'a Player) -> &'a [Card] {
+        let (start, end) = match self {
+            Street::Third => (0, 3),
+            Street::Fourth => (3, 4),
+            Street::Fifth => (4, 5),
+            Street::Sixth => (5, 6),
+            Street::Seventh => (6, 7),
+        };
+
+        player
+            .cards
+            .get(start..end.min(player.cards.len()))
+            .unwrap_or(&[])
+    }
+}
+
+/// Deals `street` to each player in seat order, a round of cards at a time.
+///
+/// Panics if the deck runs out, which can't happen dealing to `MAX_PLAYERS` or
+/// fewer from a full deck.
+pub fn deal_street(deck: &mut Deck, players: &mut [Player], street: Street) {
+    for &up in street.face_up() {
+        for player in players.iter_mut() {
+            let card = deck.deal_card().expect("The deck ran out of cards.");
+            if up {
+                player.add_up_card(card);
+            } else {
+                player.add_card(card);
+            }
+        }
+    }
+}
+
+/// Returns the seat of the player who pays the bring-in, from their first up
+/// card. In stud it's the lowest card, Aces high, and in Razz the highest,
+/// Aces low. Ties go by suit, with Clubs lowest and Spades highest.
+///
+/// Panics if a player has no up card yet.
+pub fn bring_in(game: StudGame, players: &[Player]) -> usize {
+    let up_cards = players
+        .iter()
+        .map(|player| {
+            *player
+                .up_cards()
+                .first()
+                .expect("The bring-in needs third street dealt.")
+        })
+        .collect::<Vec<Card>>();
+
+    let seats = 0..up_cards.len();
+    match game {
+        StudGame::Stud | StudGame::StudHiLo => seats
+            .min_by_key(|&i| (up_cards[i].value.value(), up_cards[i].suit.index()))
+            .unwrap(),
+        StudGame::Razz => seats
+            .max_by_key(|&i| (lowball::low_value(&up_cards[i]), up_cards[i].suit.index()))
+            .unwrap(),
+    }
+}
+
+/// The low that plays in `game`: any ace-to-five low in Razz, only eight or
+/// better in stud hi-lo, and none in stud.
+pub fn evaluate_low(game: StudGame, cards: &[Card]) -> Option<LowResult> {
+    match game {
+        StudGame::Stud => None,
+        StudGame::StudHiLo => lowball::eight_or_better(cards),
+        StudGame::Razz => Some(lowball::ace_to_five(cards)),
+    }
+}
+
+/// Splits the pot between hands of seven cards at showdown.
+pub fn shares(game: StudGame, hands: &[Vec<Card>]) -> HiLoShares {
+    match game {
+        StudGame::Stud => {
+            let highs = hands
+                .iter()
+                .map(|hand| evaluator::strength(hand))
+                .collect::<Vec<u16>>();
+            HiLoShares::split(&highs, &vec![None; hands.len()])
+        }
+        StudGame::StudHiLo => HiLoShares::stud(hands),
+        StudGame::Razz => HiLoShares::razz(hands),
+    }
+}
+
+#[cfg(test)]
+fn stud_players(hands: &[&str]) -> Vec<Player> {
+    hands
+        .iter()
+        .enumerate()
+        .map(|(i, text)| {
+            let mut player = Player::new(format!("Player {}", i + 1));
+            for (j, card) in parse_hand(text).unwrap().into_iter().enumerate() {
+                // Third street's third card is up, along with fourth to sixth.
+                if (2..6).contains(&j) {
+                    player.add_up_card(card);
+                } else {
+                    player.add_card(card);
+                }
+            }
+            player
+        })
+        .collect()
+}
+
+#[test]
+fn test_deal_streets() {
+    let mut deck = Deck::new();
+    let mut players = (0..MAX_PLAYERS)
+        .map(|i| Player::new(format!("Player {}", i + 1)))
+        .collect::<Vec<Player>>();
+
+    deal_street(&mut deck, &mut players, Street::Third);
+    assert!(players.iter().all(|player| player.cards.len() == 3));
+    assert!(players.iter().all(|player| player.up_cards().len() == 1));
+    // A round at a time, so the first player's cards are a table apart.
+    assert_eq!(players[0].cards[1], Deck::new().remaining()[MAX_PLAYERS]);
+
+    for &street in Street::ALL[1..].iter() {
+        deal_street(&mut deck, &mut players, street);
+    }
+
+    for player in players.iter() {
+        assert_eq!(player.cards.len(), HAND_SIZE);
+        assert_eq!(player.up_cards().len(), 4);
+        assert_eq!(player.down_cards().len(), 3);
+        assert_eq!(Street::Fifth.cards(player), &player.cards[4..5]);
+        assert!(!player.is_face_up(6));
+    }
+    assert_eq!(deck.len(), 52 - MAX_PLAYERS * HAND_SIZE);
+}
+
+#[test]
+fn test_bring_in() {
+    // Up cards: 2 of Hearts, 2 of Clubs, King of Spades and Ace of Diamonds.
+    let players = stud_players(&["AsKs2h", "AhKh2c", "3c4dKs", "5h6hAd"]);
+
+    // The lowest card brings it in, the 2 of Clubs below the 2 of Hearts.
+    assert_eq!(bring_in(StudGame::Stud, &players), 1);
+    assert_eq!(bring_in(StudGame::StudHiLo, &players), 1);
+    // In Razz the King is the highest card, and the Ace the lowest.
+    assert_eq!(bring_in(StudGame::Razz, &players), 2);
+}
+
+#[test]
+fn test_stud_showdown() {
+    use crate::hand_result::HandResult;
+
+    let players = stud_players(&["KcKd9h7s6c3d2h", "Ah2c3s4h9dQsKs", "QcQdQhJsJc2s5d"]);
+    let hands = players
+        .iter()
+        .map(|player| player.cards.clone())
+        .collect::<Vec<Vec<Card>>>();
+
+    let stud = shares(StudGame::Stud, &hands);
+    assert_eq!(stud.high, [0.0, 0.0, 1.0]);
+
+    // 9-4-3-2-A takes the whole pot in Razz.
+    let razz = shares(StudGame::Razz, &hands);
+    assert_eq!(razz.low, [0.0, 1.0, 0.0]);
+    assert_eq!(
+        format!("{}", evaluate_low(StudGame::Razz, &hands[1]).unwrap()),
+        "9-4-3-2-A Low"
+    );
+
+    // No eight or better low, so the full house scoops.
+    let hi_lo = shares(StudGame::StudHiLo, &hands);
+    assert_eq!(hi_lo.total(2), 1.0);
+    assert_eq!(format!("{}", HandResult::evaluate(&hands[2])), "Full House");
+}
@@ -0,0 +1,320 @@
+//! Wild cards, for video poker and home games such as deuces wild or a deck
+//! with a joker or two.
+//!
+//! A wild card stands for whichever card makes the best hand, so with enough
+//! of them Five Of A Kind is possible, and it beats a Straight Flush. A wild
+//! card playing as a natural one ties with it, so a Royal Flush made with a
+//! deuce splits the pot with a natural Royal Flush.
+
+use crate::card::*;
+use crate::combo::for_each_combination;
+use crate::evaluator;
+use crate::hand_result::HandResult;
+
+use std::fmt;
+use std::str::FromStr;
+
+use strum::IntoEnumIterator;
+
+const HAND_SIZE: usize = 5;
+const MAX_EVALUATED_CARDS: usize = 7;
+
+/// A card from a deck that may hold jokers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WildCard {
+    Natural(Card),
+    Joker,
+}
+
+impl fmt::Display for WildCard {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WildCard::Natural(card) => write!(f, "{}", card),
+            WildCard::Joker => write!(f, "Joker"),
+        }
+    }
+}
+
+impl From<Card> for WildCard {
+    fn from(card: Card) -> WildCard {
+        WildCard::Natural(card)
+    }
+}
+
+impl FromStr for WildCard {
+    type Err = ParseCardError;
+
+    /// Parses a card as `Card` does, or a joker written "Jk", "X" or "Joker".
+    fn from_str(text: &str) -> Result<WildCard, ParseCardError> {
+        match text.trim().to_lowercase().as_str() {
+            "jk" | "x" | "joker" | "🃏" => Ok(WildCard::Joker),
+            _ => text.parse().map(WildCard::Natural),
+        }
+    }
+}
+
+/// Parses a list of cards split up by spaces or commas, such as "Ah Kd Jk".
+/// Jokers may appear more than once, any other card only once.
+pub fn parse_wild_hand(text: &str) -> Result<Vec<WildCard>, ParseCardError> {
+    let mut cards = Vec::<WildCard>::new();
+
+    for word in text.split(|c: char| c.is_whitespace() || c == ',') {
+        if word.is_empty() {
+            continue;
+        }
+
+        let card = word.parse::<WildCard>()?;
+        if let WildCard::Natural(natural) = card {
+            if cards.contains(&card) {
+                return Err(ParseCardError::DuplicateCard(natural));
+            }
+        }
+
+        cards.push(card);
+    }
+
+    Ok(cards)
+}
+
+/// Which cards are wild: any jokers in the deck, and optionally every card of
+/// one value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WildRules {
+    /// How many jokers are added to the deck.
+    pub jokers: usize,
+    pub wild_value: Option<Value>,
+}
+
+impl WildRules {
+    /// Deuces wild, the four Twos are wild and there are no jokers.
+    pub fn deuces_wild() -> WildRules {
+        WildRules {
+            jokers: 0,
+            wild_value: Some(Value::Two),
+        }
+    }
+
+    /// A single joker in the deck, wild for any card.
+    pub fn joker() -> WildRules {
+        WildRules {
+            jokers: 1,
+            wild_value: None,
+        }
+    }
+
+    pub fn is_wild(&self, card: WildCard) -> bool {
+        match card {
+            WildCard::Natural(card) => Some(card.value) == self.wild_value,
+            WildCard::Joker => true,
+        }
+    }
+
+    /// A full, unshuffled deck with the jokers on the bottom.
+    pub fn deck(&self) -> Deck<WildCard> {
+        let mut cards = Deck::new()
+            .remaining()
+            .iter()
+            .map(|&card| WildCard::Natural(card))
+            .collect::<Vec<WildCard>>();
+        cards.extend((0..self.jokers).map(|_| WildCard::Joker));
+
+        Deck::from_cards(cards)
+    }
+}
+
+/// Finds the best hand that can be made from `hand` with its wild cards, under
+/// `rules`, standing for any card. The result's cards show each wild card as
+/// the card it stands for.
+pub fn evaluate(hand: &[WildCard], rules: &WildRules) -> HandResult {
+    assert!(!hand.is_empty(), "Panicked due to checking an empty hand.");
+
+    let naturals = hand
+        .iter()
+        .filter(|&&card| !rules.is_wild(card))
+        .filter_map(|card| match card {
+            WildCard::Natural(card) => Some(*card),
+            WildCard::Joker => None,
+        })
+        .collect::<Vec<Card>>();
+    let wilds = hand.len() - naturals.len();
+
+    if wilds == 0 {
+        return HandResult::check_hand(&mut naturals.clone());
+    }
+
+    if let Some(five) = five_of_a_kind(&naturals, wilds) {
+        return five;
+    }
+
+    // Short of Five Of A Kind, there's nothing to gain from a wild card standing
+    // for a card already held, so each way of picking the wild cards from the
+    // rest of the deck is tried.
+    let unseen = (CardSet::full() - CardSet::from(&naturals[..]))
+        .iter()
+        .collect::<Vec<Card>>();
+    let mut cards = naturals.clone();
+    let use_tables = hand.len() >= HAND_SIZE && hand.len() <= MAX_EVALUATED_CARDS;
+    let mut best: Option<(u16, HandResult)> = None;
+
+    for_each_combination(&unseen, wilds, |stand_ins| {
+        cards.truncate(naturals.len());
+        cards.extend_from_slice(stand_ins);
+
+        if use_tables {
+            let strength = evaluator::strength(&cards);
+            if best.as_ref().is_none_or(|(best, _)| strength > *best) {
+                best = Some((strength, HandResult::evaluate(&cards)));
+            }
+        } else {
+            let result = HandResult::check_hand(&mut cards.clone());
+            if best.as_ref().is_none_or(|(_, best)| result > *best) {
+                best = Some((0, result));
+            }
+        }
+    });
+
+    best.unwrap().1
+}
+
+/// Five Of A Kind of the highest value the wild cards can make it with, if
+/// any. With nothing but wild cards the hand is five Aces.
+fn five_of_a_kind(naturals: &[Card], wilds: usize) -> Option<HandResult> {
+    if naturals.len() + wilds < HAND_SIZE {
+        return None;
+    }
+
+    let value = Value::iter()
+        .filter(|value| {
+            naturals.iter().filter(|card| card.value == *value).count() + wilds >= HAND_SIZE
+        })
+        .max_by_key(|value| value.value())?;
+
+    let mut cards = naturals
+        .iter()
+        .filter(|card| card.value == value)
+        .cloned()
+        .collect::<Vec<Card>>();
+    let held = cards.iter().map(|card| card.suit).collect::<Vec<Suit>>();
+
+    // Wild cards take the missing suits first, then have to double up.
+    let suits = Suit::iter()
+        .filter(|suit| !held.contains(suit))
+        .chain(Suit::iter())
+        .take(HAND_SIZE - cards.len())
+        .collect::<Vec<Suit>>();
+    cards.extend(suits.into_iter().map(|suit| Card::new(suit, value)));
+
+    Some(HandResult::five_of_a_kind(cards))
+}
+
+#[test]
+fn test_parse_wild_hand() {
+    let hand = parse_wild_hand("Ah Kd Jk X").unwrap();
+    assert_eq!(
+        hand,
+        vec![
+            WildCard::Natural(Card::new(Suit::Hearts, Value::Ace)),
+            WildCard::Natural(Card::new(Suit::Diamonds, Value::King)),
+            WildCard::Joker,
+            WildCard::Joker,
+        ]
+    );
+    assert_eq!(
+        parse_wild_hand("Ah Jk Ah"),
+        Err(ParseCardError::DuplicateCard(Card::new(
+            Suit::Hearts,
+            Value::Ace
+        )))
+    );
+}
+
+#[test]
+fn test_wild_deck() {
+    let mut deck = WildRules {
+        jokers: 2,
+        wild_value: None,
+    }
+    .deck();
+    assert_eq!(deck.len(), 54);
+    assert_eq!(
+        deck.remaining()
+            .iter()
+            .filter(|&&card| card == WildCard::Joker)
+            .count(),
+        2
+    );
+
+    let mut rng = rand::thread_rng();
+    deck.shuffle(&mut rng);
+    assert_eq!(deck.deal(50).unwrap().len(), 50);
+    assert!(deck.deal(5).is_none());
+    assert_eq!(deck.len(), 4);
+}
+
+#[test]
+fn test_deuces_wild() {
+    let rules = WildRules::deuces_wild();
+    let check = |text: &str| format!("{}", evaluate(&parse_wild_hand(text).unwrap(), &rules));
+
+    assert_eq!(check("2h 2d 9c 9s 9h"), "5 Of A Kind");
+    assert_eq!(check("2h Th Jh Qh Ah"), "Straight Flush");
+    assert_eq!(check("2h 2d 2s 2c 7h"), "5 Of A Kind");
+    assert_eq!(check("2h 5d 6c 7s 9h"), "Straight");
+    assert_eq!(check("2h 5d 5c Ks 9h"), "3 Of A Kind");
+    assert_eq!(check("2h 3d 5c Ks 9h"), "Pair");
+
+    // The deuce makes a pair of Kings, the best pair it can.
+    let pair = evaluate(&parse_wild_hand("2h 3d 5c Ks 9h").unwrap(), &rules);
+    assert!(
+        pair.cards()
+            .iter()
+            .filter(|card| card.value == Value::King)
+            .count()
+            == 2
+    );
+
+    // Without wild cards the hand is checked as it is.
+    assert_eq!(check("3h 3d 9c 9s 9h"), "Full House");
+}
+
+#[test]
+fn test_five_of_a_kind_beats_a_royal_flush() {
+    let rules = WildRules::joker();
+    let five = evaluate(&parse_wild_hand("Jk Ac Ad Ah As").unwrap(), &rules);
+    let royal = evaluate(&parse_wild_hand("Jk Ah Kh Qh Jh").unwrap(), &rules);
+    let natural_royal = HandResult::check_hand(&mut parse_hand("AhKhQhJhTh").unwrap());
+
+    assert_eq!(five.category(), 9);
+    assert_eq!(five.strength(), 0);
+    assert!(five > royal);
+    // A wild Royal Flush ties with a natural one.
+    assert_eq!(royal, natural_royal);
+
+    // Five Aces beat five Kings.
+    let kings = evaluate(&parse_wild_hand("Jk Kc Kd Kh Ks").unwrap(), &rules);
+    assert!(five > kings);
+}
+
+#[test]
+fn test_wild_cards_in_seven_card_hands() {
+    let rules = WildRules {
+        jokers: 2,
+        wild_value: Some(Value::Two),
+    };
+    let check = |text: &str| evaluate(&parse_wild_hand(text).unwrap(), &rules);
+
+    // Two jokers and a deuce with a pair of Sevens make five Sevens.
+    let five = check("Jk Jk 2c 7h 7d Kc 4s");
+    assert_eq!(format!("{}", five), "5 Of A Kind");
+    assert!(five.cards().iter().all(|card| card.value == Value::Seven));
+
+    // One joker turns four hearts into an Ace high flush, not just a flush.
+    let flush = check("Jk 3h 6h 9h Jh Kc 4s");
+    assert_eq!(format!("{}", flush), "Flush");
+    assert!(flush.cards().contains(&Card::new(Suit::Hearts, Value::Ace)));
+
+    // The joker fills the gap for a Queen high straight.
+    let hand = check("Jk 8c 9d Th Qs 3c 3d");
+    assert_eq!(format!("{}", hand), "Straight");
+    assert_eq!(hand.cards()[0].value, Value::Queen);
+}